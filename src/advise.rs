@@ -0,0 +1,212 @@
+use crate::{os, util, Result};
+
+/// Advice on how memory is expected to be used.
+///
+/// Most advice is merely a hint to the kernel, and may be ignored, although
+/// some (e.g. [`Advice::DontNeed`]) alter the semantics of the memory.
+///
+/// Advice that is not supported by the OS is rejected with
+/// [`Error::Unsupported`](crate::Error::Unsupported) by [`advise`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Advice {
+  /// No special treatment (the default).
+  Normal,
+  /// Pages are expected to be accessed in a random order.
+  Random,
+  /// Pages are expected to be accessed in a sequential order.
+  Sequential,
+  /// Pages are expected to be accessed in the near future.
+  WillNeed,
+  /// Pages are not expected to be accessed in the near future.
+  ///
+  /// On Linux, the contents of private anonymous pages are discarded, and
+  /// subsequent accesses result in zero-filled pages.
+  DontNeed,
+  /// Pages are no longer needed, and may be reclaimed lazily.
+  ///
+  /// The contents of the pages are undefined until they are written to again.
+  Free,
+  /// Pages are deactivated, making them more likely to be reclaimed (Linux).
+  Cold,
+  /// Pages are reclaimed immediately (Linux).
+  PageOut,
+  /// Pages with identical contents may be merged (Linux).
+  Mergeable,
+  /// Undoes the effect of [`Advice::Mergeable`] (Linux).
+  Unmergeable,
+  /// Pages are backed by transparent huge pages (Linux).
+  HugePage,
+  /// Pages are not backed by transparent huge pages (Linux).
+  NoHugePage,
+  /// Pages are prefaulted, as if they were read from (Linux).
+  PopulateRead,
+  /// Pages are prefaulted, as if they were written to (Linux).
+  PopulateWrite,
+  /// Pages are excluded from core dumps (Linux & FreeBSD).
+  DontDump,
+  /// Undoes the effect of [`Advice::DontDump`] (Linux & FreeBSD).
+  DoDump,
+  /// Pages are not made available to child processes (Linux).
+  DontFork,
+  /// Undoes the effect of [`Advice::DontFork`] (Linux).
+  DoFork,
+  /// Pages are zero-filled in child processes (Linux).
+  WipeOnFork,
+  /// Undoes the effect of [`Advice::WipeOnFork`] (Linux).
+  KeepOnFork,
+}
+
+impl Advice {
+  /// Returns the name of the advice, as used in errors.
+  pub(crate) fn name(self) -> &'static str {
+    match self {
+      Advice::Normal => "normal",
+      Advice::Random => "random",
+      Advice::Sequential => "sequential",
+      Advice::WillNeed => "will_need",
+      Advice::DontNeed => "dont_need",
+      Advice::Free => "free",
+      Advice::Cold => "cold",
+      Advice::PageOut => "page_out",
+      Advice::Mergeable => "mergeable",
+      Advice::Unmergeable => "unmergeable",
+      Advice::HugePage => "huge_page",
+      Advice::NoHugePage => "no_huge_page",
+      Advice::PopulateRead => "populate_read",
+      Advice::PopulateWrite => "populate_write",
+      Advice::DontDump => "dont_dump",
+      Advice::DoDump => "do_dump",
+      Advice::DontFork => "dont_fork",
+      Advice::DoFork => "do_fork",
+      Advice::WipeOnFork => "wipe_on_fork",
+      Advice::KeepOnFork => "keep_on_fork",
+    }
+  }
+
+  /// Returns whether the advice discards (or zero-fills) the memory's
+  /// contents, in the process itself or its children.
+  pub(crate) fn is_destructive(self) -> bool {
+    matches!(self, Advice::DontNeed | Advice::Free | Advice::WipeOnFork)
+  }
+}
+
+/// Advises the OS on how one or more pages of memory are expected to be used.
+///
+/// The address range may overlap one or more pages, and if so, all pages
+/// spanning the range will be advised.
+///
+/// # Parameters
+///
+/// - The range is `[address, address + size)`
+/// - The address is rounded down to the closest page boundary.
+/// - The size may not be zero.
+/// - The size is rounded up to the closest page boundary, relative to the
+///   address.
+///
+/// # Errors
+///
+/// - If an interaction with the underlying operating system fails, an error
+///   will be returned.
+/// - If size is zero,
+///   [`Error::InvalidParameter`](crate::Error::InvalidParameter) will be
+///   returned.
+/// - If the advice is not supported by the OS (or kernel version),
+///   [`Error::Unsupported`](crate::Error::Unsupported) will be returned.
+///
+/// # Safety
+///
+/// Some advice (e.g. [`Advice::DontNeed`] and [`Advice::Free`]) discards the
+/// contents of the pages, which can violate memory safety.
+///
+/// # Implementation
+///
+/// This function is implemented using `madvise` on POSIX. On Windows,
+/// [`Advice::WillNeed`] uses `PrefetchVirtualMemory` and [`Advice::Free`] uses
+/// `DiscardVirtualMemory`, whilst all other advice is unsupported.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> region::Result<()> {
+/// use region::{Advice, Protection};
+///
+/// let memory = region::alloc(region::page::size() * 4, Protection::READ_WRITE)?;
+/// unsafe { region::advise(memory.as_ptr::<()>(), memory.len(), Advice::Sequential)? };
+/// # Ok(())
+/// # }
+/// ```
+#[inline]
+pub unsafe fn advise<T>(address: *const T, size: usize, advice: Advice) -> Result<()> {
+  let (address, size) = util::round_to_page_boundaries(address, size)?;
+  os::advise(address.cast(), size, advice)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{alloc, page, Error, Protection};
+
+  #[test]
+  fn advise_rejects_empty_range() {
+    let result = unsafe { advise(page::size() as *const (), 0, Advice::Normal) };
+    assert!(matches!(result, Err(Error::InvalidParameter(_))));
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn advise_accepts_access_patterns() -> Result<()> {
+    let memory = alloc(page::size() * 2, Protection::READ_WRITE)?;
+
+    for &advice in &[
+      Advice::Random,
+      Advice::Sequential,
+      Advice::WillNeed,
+      Advice::Normal,
+    ] {
+      unsafe { advise(memory.as_ptr::<u8>(), memory.len(), advice)? };
+    }
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn advise_dont_need_discards_contents() -> Result<()> {
+    let memory = alloc(page::size(), Protection::READ_WRITE)?;
+    let data = memory.as_ptr::<u8>() as *mut u8;
+
+    unsafe {
+      *data = 0xFF;
+      advise(data, 1, Advice::DontNeed)?;
+
+      // Only Linux guarantees that discarded anonymous pages are zero-filled
+      if cfg!(any(target_os = "linux", target_os = "android")) {
+        assert_eq!(*data, 0);
+      }
+    }
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn advise_is_reported_by_vm_flags() -> Result<()> {
+    use crate::VmFlags;
+
+    let memory = alloc(page::size(), Protection::READ_WRITE)?;
+    unsafe { advise(memory.as_ptr::<u8>(), 1, Advice::DontDump)? };
+
+    let region = crate::query_detailed(memory.as_ptr::<u8>())?;
+    assert!(region.vm_flags().unwrap().contains(VmFlags::DONTDUMP));
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(not(any(target_os = "linux", target_os = "android")))]
+  fn advise_rejects_unsupported_advice() -> Result<()> {
+    let memory = alloc(page::size(), Protection::READ_WRITE)?;
+    let result = unsafe { advise(memory.as_ptr::<u8>(), 1, Advice::WipeOnFork) };
+
+    assert!(matches!(result, Err(Error::Unsupported(_))));
+    Ok(())
+  }
+}
@@ -1,15 +1,23 @@
 use std::mem::ManuallyDrop;
+use std::ops::Deref;
 
-use crate::{os, page, util, Error, Protection, Result};
+use crate::{os, page, util, Advice, Error, ProtectGuard, Protection, QueryIter, Region, Result};
 
 /// A handle to an owned region of memory.
 ///
 /// This handle does not dereference to a slice, since the underlying memory may
-/// have been created with [`Protection::NONE`].
+/// have been created with [`Protection::NONE`]. Use [`Allocation::as_slice`]
+/// for checked access to its contents.
 #[allow(clippy::len_without_is_empty)]
 pub struct Allocation {
   base: *const (),
   size: usize,
+  /// Size of the guard pages preceding and following the allocation
+  guard: (usize, usize),
+  /// Size of the pages backing the allocation (i.e. the huge page size)
+  page_size: usize,
+  /// Protection of the allocation, unless unknown (or mixed)
+  protection: Option<Protection>,
 }
 
 impl Allocation {
@@ -61,10 +69,481 @@ impl Allocation {
     self.size
   }
 
+  /// Returns the allocation as a byte slice.
+  ///
+  /// # Errors
+  ///
+  /// - If the allocation is not readable,
+  ///   [`Error::InsufficientProtection`] will be returned.
+  /// - If the allocation's protection is unknown (e.g. it has been created
+  ///   using [`Allocation::from_raw_parts`]), and querying it fails, an error
+  ///   will be returned.
+  #[inline]
+  pub fn as_slice(&self) -> Result<&[u8]> {
+    self.ensure_protection(Protection::READ)?;
+    Ok(unsafe { std::slice::from_raw_parts(self.as_ptr(), self.size) })
+  }
+
+  /// Returns the allocation as a mutable byte slice.
+  ///
+  /// # Errors
+  ///
+  /// - If the allocation is not readable and writable,
+  ///   [`Error::InsufficientProtection`] will be returned.
+  /// - If the allocation's protection is unknown (e.g. it has been created
+  ///   using [`Allocation::from_raw_parts`]), and querying it fails, an error
+  ///   will be returned.
+  #[inline]
+  pub fn as_mut_slice(&mut self) -> Result<&mut [u8]> {
+    self.ensure_protection(Protection::READ_WRITE)?;
+    Ok(unsafe { std::slice::from_raw_parts_mut(self.as_mut_ptr(), self.size) })
+  }
+
+  /// Verifies that the allocation provides (at least) a specific protection.
+  fn ensure_protection(&self, required: Protection) -> Result<()> {
+    let protection = match self.protection {
+      Some(protection) => protection,
+      None => self.regions()?.try_fold(Protection::all(), |acc, region| {
+        Ok(acc & region?.protection())
+      })?,
+    };
+
+    if protection.contains(required) {
+      Ok(())
+    } else {
+      Err(Error::InsufficientProtection(required))
+    }
+  }
+
+  /// Changes the memory protection of the allocation.
+  ///
+  /// This is the equivalent of [`protect`](crate::protect), spanning the
+  /// entire allocation. It's safe, since the allocation is exclusively
+  /// borrowed (and owns its memory).
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  /// - If the protection exceeds the allocation's maximum protection (e.g. a
+  ///   shared mapping of a read-only file), [`Error::MaxProtectionExceeded`]
+  ///   will be returned, once the system call has failed and where the
+  ///   maximum protection is known (see [`protect`](crate::protect)).
+  /// - If the protection would extend the change beyond the allocation (i.e.
+  ///   `Protection::GROWSDOWN` or `Protection::GROWSUP` on Linux),
+  ///   [`Error::InvalidParameter`] will be returned.
+  #[inline]
+  pub fn protect(&mut self, protection: Protection) -> Result<()> {
+    ensure_contained(protection)?;
+
+    // The protection is unknown if the operation fails midway
+    self.protection = None;
+    unsafe { os::protect(self.base, self.size, protection)? };
+    self.protection = Some(protection);
+    Ok(())
+  }
+
+  /// Changes the memory protection of one or more pages of the allocation.
+  ///
+  /// This is the equivalent of [`protect`](crate::protect), with an offset
+  /// relative to the allocation's base address.
+  ///
+  /// # Parameters
+  ///
+  /// - The offset is rounded down to the closest page boundary.
+  /// - The length may not be zero.
+  /// - The length is rounded up to the closest page boundary, relative to the
+  ///   offset.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  /// - If the length is zero, the range exceeds the allocation, or the
+  ///   protection would extend the change beyond it (see
+  ///   [`Allocation::protect`]), [`Error::InvalidParameter`] will be returned.
+  #[inline]
+  pub fn protect_range(&mut self, offset: usize, len: usize, protection: Protection) -> Result<()> {
+    ensure_contained(protection)?;
+    let (address, size) = util::round_subrange_to_page_boundaries(self.as_range(), offset, len)?;
+
+    // The protection is mixed unless the range spans the entire allocation
+    self.protection = None;
+    unsafe { os::protect(address, size, protection)? };
+
+    if size == self.size {
+      self.protection = Some(protection);
+    }
+
+    Ok(())
+  }
+
+  /// Temporarily changes the memory protection of the allocation.
+  ///
+  /// This is the equivalent of [`protect_with_handle`](crate::protect_with_handle),
+  /// spanning the entire allocation. The returned guard dereferences to the
+  /// allocation, and its previous protection is restored once the guard is
+  /// dropped.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  /// - If the protection would extend the change beyond the allocation (see
+  ///   [`Allocation::protect`]), [`Error::InvalidParameter`] will be returned.
+  #[inline]
+  pub fn protect_with_handle(
+    &mut self,
+    protection: Protection,
+  ) -> Result<AllocationProtectGuard<'_>> {
+    ensure_contained(protection)?;
+    let guard = unsafe { crate::protect_with_handle(self.base, self.size, protection)? };
+    let previous = self.protection.replace(protection);
+
+    Ok(AllocationProtectGuard {
+      allocation: self,
+      previous,
+      _guard: guard,
+    })
+  }
+
+  /// Locks the allocation to RAM.
+  ///
+  /// This is the equivalent of [`lock`](crate::lock), spanning the entire
+  /// allocation. The allocation remains locked until it's either unlocked
+  /// using [`Allocation::unlock`], or freed.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  #[inline]
+  pub fn lock(&self) -> Result<()> {
+    os::lock(self.base, self.size)
+  }
+
+  /// Unlocks the allocation from RAM.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  #[inline]
+  pub fn unlock(&self) -> Result<()> {
+    os::unlock(self.base, self.size)
+  }
+
+  /// Queries the OS for the region at the allocation's base address.
+  ///
+  /// The region may extend beyond the allocation, if adjacent memory shares
+  /// its properties.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  #[inline]
+  pub fn query(&self) -> Result<Region> {
+    crate::query(self.base)
+  }
+
+  /// Queries the OS for the regions that span the allocation.
+  ///
+  /// The first and last regions may extend beyond the allocation, if adjacent
+  /// memory shares their properties.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  #[inline]
+  pub fn regions(&self) -> Result<QueryIter> {
+    crate::query_range(self.base, self.size)
+  }
+
+  /// Resizes the allocation, preserving its contents.
+  ///
+  /// If the allocation grows, it's extended in place if the address space
+  /// following it is available. Otherwise, it's moved to a new address if
+  /// `may_move` is set, or an error is returned whilst the allocation remains
+  /// untouched. Any pages that are added have the protection of the
+  /// allocation's last page.
+  ///
+  /// # Parameters
+  ///
+  /// - The size may not be zero.
+  /// - The size is rounded up to the closest page boundary.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails (e.g. the
+  ///   allocation cannot grow in place), an error will be returned.
+  /// - If size is zero, [`Error::InvalidParameter`] will be returned.
+  /// - If the allocation has guard pages, or the OS does not support resizing
+  ///   (see below), [`Error::Unsupported`] will be returned.
+  ///
+  /// # OS-Specific Behavior
+  ///
+  /// Allocations can only be shrunk on Unix systems other than Linux, and
+  /// cannot be resized at all on Windows.
+  ///
+  /// On Linux, an allocation can only grow if it spans a single mapping of the
+  /// kernel. Allocations with pages of differing properties (e.g. after using
+  /// [`Allocation::protect_range`]) span multiple mappings, and fail to grow
+  /// with a system call error (`EFAULT`), whilst they remain untouched.
+  ///
+  /// # Implementation
+  ///
+  /// This function is implemented using `mremap` on Linux, and `munmap` on
+  /// other POSIX systems.
+  #[inline]
+  pub fn resize(&mut self, size: usize, may_move: bool) -> Result<()> {
+    if size == 0 {
+      return Err(Error::InvalidParameter("size"));
+    }
+
+    if self.guard != (0, 0) {
+      return Err(Error::Unsupported("guard_pages"));
+    }
+
+    let size = page::ceil(size as *const ()) as usize;
+
+    if size != self.size {
+      self.base = unsafe { os::resize(self.base, self.size, size, may_move)? };
+      self.size = size;
+    }
+
+    Ok(())
+  }
+
+  /// Moves the allocation to a specific address, preserving its contents.
+  ///
+  /// # Parameters
+  ///
+  /// - The address must be aligned to a page boundary.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails (e.g. the
+  ///   destination overlaps the allocation), an error will be returned.
+  /// - If the address is not aligned to a page boundary,
+  ///   [`Error::InvalidParameter`] will be returned.
+  /// - If the allocation has guard pages, or the OS is not Linux,
+  ///   [`Error::Unsupported`] will be returned.
+  ///
+  /// # Safety
+  ///
+  /// Any existing mapping at the destination is replaced, which can violate
+  /// memory safety (similar to [`alloc_at`]).
+  ///
+  /// # Implementation
+  ///
+  /// This function is implemented using `mremap` with `MREMAP_FIXED`.
+  #[inline]
+  pub unsafe fn move_to<T>(&mut self, address: *const T) -> Result<()> {
+    if address as usize & (page::size() - 1) != 0 {
+      return Err(Error::InvalidParameter("address"));
+    }
+
+    if self.guard != (0, 0) {
+      return Err(Error::Unsupported("guard_pages"));
+    }
+
+    os::move_to(self.base, self.size, address.cast())?;
+    self.base = address.cast();
+    Ok(())
+  }
+
+  /// Advises the OS on how the allocation's memory is expected to be used.
+  ///
+  /// This is the equivalent of [`advise`](crate::advise), spanning the entire
+  /// allocation. Advice that discards the allocation's contents (i.e.
+  /// [`Advice::DontNeed`], [`Advice::Free`] and [`Advice::WipeOnFork`]) is
+  /// rejected, since its contents may be referenced (e.g. by
+  /// [`Allocation::as_slice`]). Such advice requires the unsafe
+  /// [`advise`](crate::advise) function.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  /// - If the advice discards the allocation's contents,
+  ///   [`Error::InvalidParameter`] will be returned.
+  /// - If the advice is not supported by the OS, [`Error::Unsupported`] will be
+  ///   returned.
+  #[inline]
+  pub fn advise(&mut self, advice: Advice) -> Result<()> {
+    if advice.is_destructive() {
+      return Err(Error::InvalidParameter("advice"));
+    }
+
+    unsafe { os::advise(self.base, self.size, advice) }
+  }
+
+  /// Flushes modifications of a shared file mapping to the file, and waits for
+  /// the writes to complete.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  ///
+  /// # OS-Specific Behavior
+  ///
+  /// On POSIX, this is a no-op for anonymous allocations.
+  ///
+  /// On Windows, the writes are only initiated, and not waited for (i.e. this
+  /// is the equivalent of [`Allocation::flush_async`]). The file's handle must
+  /// be flushed using [`File::sync_data`](std::fs::File::sync_data) to wait
+  /// for the writes to reach the disk.
+  ///
+  /// # Implementation
+  ///
+  /// This function is implemented using `FlushViewOfFile` on Windows, and
+  /// `msync` with `MS_SYNC` on POSIX.
+  #[inline]
+  pub fn flush(&self) -> Result<()> {
+    os::flush(self.base, self.size, true)
+  }
+
+  /// Flushes modifications of a shared file mapping to the file, without
+  /// waiting for the writes to complete.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  ///
+  /// # Implementation
+  ///
+  /// This function is implemented using `FlushViewOfFile` on Windows, and
+  /// `msync` with `MS_ASYNC` on POSIX.
+  #[inline]
+  pub fn flush_async(&self) -> Result<()> {
+    os::flush(self.base, self.size, false)
+  }
+
+  /// Splits the allocation into two, at a page aligned offset.
+  ///
+  /// The first allocation spans `[0, offset)`, and the second `[offset, len)`.
+  /// Each allocation only frees its own pages (and guard pages) once it's
+  /// dropped, thus either of them may be handed off independently.
+  ///
+  /// # Parameters
+  ///
+  /// - The offset must be aligned to a page boundary (or the huge page size,
+  ///   if the allocation uses huge pages).
+  /// - The offset may neither be zero, nor exceed the allocation.
+  ///
+  /// # Errors
+  ///
+  /// The allocation is returned untouched alongside the error, if any.
+  ///
+  /// - If the offset is not aligned to a (huge) page boundary, or does not
+  ///   split the allocation in two, [`Error::InvalidParameter`] will be
+  ///   returned.
+  /// - If the OS is Windows, [`Error::Unsupported`] will be returned, since
+  ///   only entire allocations can be freed.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// # fn main() -> region::Result<()> {
+  /// use region::{page, Protection};
+  ///
+  /// # if cfg!(unix) {
+  /// let memory = region::alloc(page::size() * 4, Protection::READ_WRITE)?;
+  /// let (head, tail) = memory
+  ///   .split_at(page::size())
+  ///   .map_err(|(error, _)| error)?;
+  ///
+  /// assert_eq!(head.len(), page::size());
+  /// assert_eq!(tail.len(), page::size() * 3);
+  /// assert_eq!(head.as_range().end, tail.as_range().start);
+  /// # }
+  /// # Ok(())
+  /// # }
+  /// ```
+  #[inline]
+  pub fn split_at(
+    self,
+    offset: usize,
+  ) -> std::result::Result<(Allocation, Allocation), (Error, Allocation)> {
+    if offset == 0 || offset >= self.size || offset & (self.page_size - 1) != 0 {
+      return Err((Error::InvalidParameter("offset"), self));
+    }
+
+    if cfg!(windows) {
+      return Err((Error::Unsupported("split_at"), self));
+    }
+
+    let this = ManuallyDrop::new(self);
+    let head = Allocation {
+      base: this.base,
+      size: offset,
+      guard: (this.guard.0, 0),
+      page_size: this.page_size,
+      protection: this.protection,
+    };
+    let tail = Allocation {
+      base: (this.base as usize + offset) as *const (),
+      size: this.size - offset,
+      guard: (0, this.guard.1),
+      page_size: this.page_size,
+      protection: this.protection,
+    };
+
+    Ok((head, tail))
+  }
+
+  /// Merges the allocation with an adjacent allocation.
+  ///
+  /// The allocations may be provided in any order, but the usable memory of
+  /// one must immediately follow the other's (i.e. without any guard pages
+  /// in-between). This is the inverse of [`Allocation::split_at`].
+  ///
+  /// # Errors
+  ///
+  /// If the allocations are not adjacent, or the OS is Windows (where
+  /// allocations cannot be merged), both allocations are returned untouched.
+  #[inline]
+  pub fn try_merge(
+    self,
+    other: Allocation,
+  ) -> std::result::Result<Allocation, (Allocation, Allocation)> {
+    let (head, tail) = if self.as_range().end == other.as_range().start {
+      (&self, &other)
+    } else if other.as_range().end == self.as_range().start {
+      (&other, &self)
+    } else {
+      return Err((self, other));
+    };
+
+    if cfg!(windows) || head.guard.1 != 0 || tail.guard.0 != 0 || head.page_size != tail.page_size {
+      return Err((self, other));
+    }
+
+    let merged = Allocation {
+      base: head.base,
+      size: head.size + tail.size,
+      guard: (head.guard.0, tail.guard.1),
+      page_size: head.page_size,
+      protection: if head.protection == tail.protection {
+        head.protection
+      } else {
+        None
+      },
+    };
+
+    std::mem::forget((self, other));
+    Ok(merged)
+  }
+
   /// Decomposes an `Allocation` into its raw components: `(pointer, length)`.
   ///
   /// After calling this function, the caller is responsible for the previously
-  /// managed allocation.
+  /// managed allocation. The components only describe the usable memory, thus
+  /// any guard pages of the allocation must be accounted for by the caller.
   ///
   /// For creating an `Allocation` from raw components, see [`Self::from_raw_parts`].
   #[inline]
@@ -88,6 +567,9 @@ impl Allocation {
     Self {
       base: ptr as *const (),
       size: length,
+      guard: (0, 0),
+      page_size: page::size(),
+      protection: None,
     }
   }
 }
@@ -95,11 +577,61 @@ impl Allocation {
 impl Drop for Allocation {
   #[inline]
   fn drop(&mut self) {
-    let result = unsafe { os::free(self.base, self.size) };
+    let (before, after) = self.guard;
+    let base = (self.base as usize - before) as *const ();
+    let result = unsafe { os::free(base, before + self.size + after) };
     debug_assert!(result.is_ok(), "freeing region: {:?}", result);
   }
 }
 
+/// A RAII implementation of a scoped protection guard, for an [`Allocation`].
+///
+/// When this structure is dropped (falls out of scope), the allocation's
+/// protection will be reset. It dereferences to the allocation, albeit
+/// immutably, since the allocation may not be resized whilst it's guarded.
+///
+/// This `struct` is created by [`Allocation::protect_with_handle`].
+#[must_use]
+pub struct AllocationProtectGuard<'a> {
+  allocation: &'a mut Allocation,
+  previous: Option<Protection>,
+  _guard: ProtectGuard,
+}
+
+impl Deref for AllocationProtectGuard<'_> {
+  type Target = Allocation;
+
+  #[inline(always)]
+  fn deref(&self) -> &Allocation {
+    self.allocation
+  }
+}
+
+impl Drop for AllocationProtectGuard<'_> {
+  #[inline]
+  fn drop(&mut self) {
+    // The memory's protection is restored once the inner guard is dropped
+    self.allocation.protection = self.previous;
+  }
+}
+
+/// Verifies that a protection change only affects the pages it's applied to.
+///
+/// Protection flags that extend the change to adjacent pages, may affect memory
+/// that the allocation does not own.
+fn ensure_contained(protection: Protection) -> Result<()> {
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  let uncontained = Protection::GROWSDOWN | Protection::GROWSUP;
+  #[cfg(not(any(target_os = "linux", target_os = "android")))]
+  let uncontained = Protection::NONE;
+
+  if protection.intersects(uncontained) {
+    Err(Error::InvalidParameter("protection"))
+  } else {
+    Ok(())
+  }
+}
+
 /// Allocates one or more pages of memory, with a defined protection.
 ///
 /// This function provides a very simple interface for allocating anonymous
@@ -114,7 +646,7 @@ impl Drop for Allocation {
 /// # Errors
 ///
 /// - If an interaction with the underlying operating system fails, an error
-/// will be returned.
+///   will be returned.
 /// - If size is zero, [`Error::InvalidParameter`] will be returned.
 ///
 /// # OS-Specific Behavior
@@ -146,16 +678,7 @@ impl Drop for Allocation {
 /// ```
 #[inline]
 pub fn alloc(size: usize, protection: Protection) -> Result<Allocation> {
-  if size == 0 {
-    return Err(Error::InvalidParameter("size"));
-  }
-
-  let size = page::ceil(size as *const ()) as usize;
-
-  unsafe {
-    let base = os::alloc(std::ptr::null::<()>(), size, protection)?;
-    Ok(Allocation { base, size })
-  }
+  AllocOptions::new().alloc(size, protection)
 }
 
 /// Allocates one or more pages of memory, at a specific address, with a defined
@@ -181,15 +704,299 @@ pub fn alloc(size: usize, protection: Protection) -> Result<Allocation> {
 /// # Errors
 ///
 /// - If an interaction with the underlying operating system fails, an error
-/// will be returned.
+///   will be returned.
 /// - If size is zero, [`Error::InvalidParameter`] will be returned.
 #[inline]
 pub fn alloc_at<T>(address: *const T, size: usize, protection: Protection) -> Result<Allocation> {
-  let (address, size) = util::round_to_page_boundaries(address, size)?;
+  AllocOptions::new().alloc_at(address, size, protection)
+}
+
+/// Options and flags which can be used to configure how memory is allocated.
+///
+/// This builder exposes the ability to configure how an [`Allocation`] is
+/// created. The [`alloc`] and [`alloc_at`] functions are aliases for
+/// allocating memory using the default options.
+///
+/// Generally speaking, when using `AllocOptions`, you'll first call
+/// [`AllocOptions::new`], then chain calls to methods to set each option, then
+/// call [`AllocOptions::alloc`] or [`AllocOptions::alloc_at`].
+///
+/// # OS-Specific Behavior
+///
+/// Options that are merely hints (i.e. [`AllocOptions::populate`] and
+/// [`AllocOptions::stack`]) are ignored where unsupported. For all other
+/// options, [`Error::Unsupported`] is returned when allocating, if the option
+/// is unsupported by the OS.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> region::Result<()> {
+/// use region::{AllocOptions, Protection};
+///
+/// let memory = AllocOptions::new()
+///   .populate(true)
+///   .alloc(100, Protection::READ_WRITE)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AllocOptions {
+  pub(crate) populate: bool,
+  pub(crate) no_reserve: bool,
+  pub(crate) stack: bool,
+  pub(crate) grows_down: bool,
+  pub(crate) map_32bit: bool,
+  pub(crate) huge_page_size: Option<usize>,
+  pub(crate) shared: bool,
+  pub(crate) guard_pages: (usize, usize),
+}
+
+impl AllocOptions {
+  /// Creates a blank new set of options, ready for configuration.
+  ///
+  /// All options are initially set to `false`.
+  #[inline]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sets the option for pre-faulting all pages of the allocation.
+  ///
+  /// This avoids page faults when the memory is accessed later on.
+  ///
+  /// # Implementation
+  ///
+  /// This uses `MAP_POPULATE` on Linux, and is ignored elsewhere.
+  #[inline]
+  pub fn populate(&mut self, populate: bool) -> &mut Self {
+    self.populate = populate;
+    self
+  }
 
-  unsafe {
-    let base = os::alloc(address.cast(), size, protection)?;
-    Ok(Allocation { base, size })
+  /// Sets the option for not reserving swap space for the allocation.
+  ///
+  /// When swap space is not reserved, writes may fail with `SIGSEGV` if no
+  /// physical memory is available.
+  ///
+  /// # Implementation
+  ///
+  /// This uses `MAP_NORESERVE`, and is available on Linux, Illumos & NetBSD.
+  #[inline]
+  pub fn no_reserve(&mut self, no_reserve: bool) -> &mut Self {
+    self.no_reserve = no_reserve;
+    self
+  }
+
+  /// Sets the option for allocating memory suitable for a thread's stack.
+  ///
+  /// # Implementation
+  ///
+  /// This uses `MAP_STACK` on Linux & BSDs, and is ignored elsewhere.
+  #[inline]
+  pub fn stack(&mut self, stack: bool) -> &mut Self {
+    self.stack = stack;
+    self
+  }
+
+  /// Sets the option for allocating memory that grows downwards.
+  ///
+  /// The allocation is expanded by the kernel when the page below it is
+  /// accessed (as for the main thread's stack).
+  ///
+  /// # Implementation
+  ///
+  /// This uses `MAP_GROWSDOWN`, and is only available on Linux.
+  #[inline]
+  pub fn grows_down(&mut self, grows_down: bool) -> &mut Self {
+    self.grows_down = grows_down;
+    self
+  }
+
+  /// Sets the option for placing the allocation within the first 2GB of the
+  /// address space.
+  ///
+  /// # Implementation
+  ///
+  /// This uses `MAP_32BIT`, and is only available on Linux & FreeBSD (x86-64).
+  #[inline]
+  pub fn map_32bit(&mut self, map_32bit: bool) -> &mut Self {
+    self.map_32bit = map_32bit;
+    self
+  }
+
+  /// Sets the size of the huge pages used for the allocation, if any.
+  ///
+  /// The size must be a power of two supported by the system (e.g. 2MB or
+  /// 1GB on x86-64), larger than the regular page size, and the allocation's
+  /// size is rounded up to a multiple of it. There must be enough huge pages
+  /// reserved by the system for the allocation to succeed.
+  ///
+  /// # Implementation
+  ///
+  /// This uses `MAP_HUGETLB`, and is only available on Linux.
+  #[inline]
+  pub fn huge_page_size(&mut self, page_size: Option<usize>) -> &mut Self {
+    self.huge_page_size = page_size;
+    self
+  }
+
+  /// Sets the option for sharing the allocation with child processes.
+  ///
+  /// By default, allocations are private, meaning that they are copied (on
+  /// write) into child processes. Shared allocations are instead accessed by
+  /// both the parent and its children.
+  ///
+  /// # Implementation
+  ///
+  /// This uses `MAP_SHARED`, and is only available on Unix.
+  #[inline]
+  pub fn shared(&mut self, shared: bool) -> &mut Self {
+    self.shared = shared;
+    self
+  }
+
+  /// Sets the number of inaccessible guard pages placed before and after the
+  /// allocation.
+  ///
+  /// Accessing a guard page results in a fault (e.g. `SIGSEGV`), which makes
+  /// overflows detectable. The resulting [`Allocation`] only spans the usable
+  /// memory, but the guard pages are freed alongside it. When using huge
+  /// pages, each guard page is the size of a huge page.
+  ///
+  /// Guard pages are not supported by [`AllocOptions::alloc_at`].
+  ///
+  /// # Implementation
+  ///
+  /// The guard pages are allocated as part of the same mapping, and protected
+  /// with [`Protection::NONE`].
+  #[inline]
+  pub fn guard_pages(&mut self, before: usize, after: usize) -> &mut Self {
+    self.guard_pages = (before, after);
+    self
+  }
+
+  /// Allocates one or more pages of memory, with a defined protection.
+  ///
+  /// This is the equivalent of [`alloc`], with the options specified by
+  /// `self`.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  /// - If size is zero, the huge page size is invalid, or the allocation
+  ///   (including guard pages) overflows the address space,
+  ///   [`Error::InvalidParameter`] will be returned.
+  /// - If an option is not supported by the OS, [`Error::Unsupported`] will be
+  ///   returned.
+  #[inline]
+  pub fn alloc(&self, size: usize, protection: Protection) -> Result<Allocation> {
+    if size == 0 {
+      return Err(Error::InvalidParameter("size"));
+    }
+
+    let size = self.round_size(size)?;
+    unsafe { self.map(std::ptr::null(), size, protection) }
+  }
+
+  /// Allocates one or more pages of memory, at a specific address, with a
+  /// defined protection.
+  ///
+  /// This is the equivalent of [`alloc_at`], with the options specified by
+  /// `self`. When using huge pages, the address must be aligned to the huge
+  /// page size. Guard pages are not supported, since they would replace any
+  /// existing mappings surrounding the address.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  /// - If size is zero, the huge page size is invalid, the address is not
+  ///   aligned to it, or guard pages are used, [`Error::InvalidParameter`]
+  ///   will be returned.
+  /// - If an option is not supported by the OS, [`Error::Unsupported`] will be
+  ///   returned.
+  #[inline]
+  pub fn alloc_at<T>(
+    &self,
+    address: *const T,
+    size: usize,
+    protection: Protection,
+  ) -> Result<Allocation> {
+    let (address, size) = util::round_to_page_boundaries(address, size)?;
+    let size = self.round_size(size)?;
+
+    if self.guard_pages != (0, 0) {
+      return Err(Error::InvalidParameter("guard_pages"));
+    }
+
+    if let Some(page_size) = self.huge_page_size {
+      if address as usize & (page_size - 1) != 0 {
+        return Err(Error::InvalidParameter("address"));
+      }
+    }
+
+    unsafe { self.map(address.cast(), size, protection) }
+  }
+
+  /// Maps the allocation, surrounded by its guard pages.
+  ///
+  /// Guard pages are only supported when the OS decides the address.
+  unsafe fn map(
+    &self,
+    address: *const (),
+    size: usize,
+    protection: Protection,
+  ) -> Result<Allocation> {
+    let page_size = self.huge_page_size.unwrap_or_else(page::size);
+    let (before, after) = self.guard_pages;
+    let guard = before
+      .checked_mul(page_size)
+      .and_then(|before| Some((before, after.checked_mul(page_size)?)))
+      .ok_or(Error::InvalidParameter("guard_pages"))?;
+    let total = size
+      .checked_add(guard.0)
+      .and_then(|size| size.checked_add(guard.1))
+      .ok_or(Error::InvalidParameter("guard_pages"))?;
+
+    let base = os::alloc(address, total, protection, self)?;
+    let allocation = Allocation {
+      base: (base as usize + guard.0) as *const (),
+      size,
+      guard,
+      page_size,
+      protection: Some(protection),
+    };
+
+    // The allocation is freed when dropped, in case the protection fails
+    if guard.0 > 0 {
+      os::protect(base, guard.0, Protection::NONE)?;
+    }
+
+    if guard.1 > 0 {
+      os::protect(
+        allocation.as_range().end as *const (),
+        guard.1,
+        Protection::NONE,
+      )?;
+    }
+
+    Ok(allocation)
+  }
+
+  /// Rounds a non-zero size up to the closest (huge) page boundary.
+  fn round_size(&self, size: usize) -> Result<usize> {
+    match self.huge_page_size {
+      Some(page_size) if !page_size.is_power_of_two() || page_size <= page::size() => {
+        Err(Error::InvalidParameter("huge_page_size"))
+      }
+      Some(page_size) => size
+        .checked_add(page_size - 1)
+        .map(|size| size & !(page_size - 1))
+        .ok_or(Error::InvalidParameter("size")),
+      None => Ok(page::ceil(size as *const ()) as usize),
+    }
   }
 }
 
@@ -263,4 +1070,483 @@ mod tests {
     assert_eq!(memory.len(), page::size());
     Ok(())
   }
+
+  #[test]
+  fn alloc_options_reject_invalid_huge_page_size() {
+    for page_size in [0, 3, page::size() / 2, page::size()].iter() {
+      assert!(matches!(
+        AllocOptions::new()
+          .huge_page_size(Some(*page_size))
+          .alloc(1, Protection::READ_WRITE),
+        Err(Error::InvalidParameter("huge_page_size"))
+      ));
+    }
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn alloc_options_can_allocate_shared_region() -> Result<()> {
+    let memory = AllocOptions::new()
+      .shared(true)
+      .alloc(1, Protection::READ_WRITE)?;
+
+    let region = crate::query(memory.as_ptr::<()>())?;
+    assert!(region.is_shared());
+    assert_eq!(region.protection(), Protection::READ_WRITE);
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn alloc_options_are_applied() -> Result<()> {
+    use crate::{query_detailed, VmFlags};
+
+    let memory = AllocOptions::new()
+      .populate(true)
+      .no_reserve(true)
+      .alloc(page::size() * 4, Protection::READ_WRITE)?;
+
+    let residency = page::resident(memory.as_ptr::<u8>(), memory.len())?;
+    assert_eq!(residency.count(), 4);
+
+    let flags = query_detailed(memory.as_ptr::<()>())?.vm_flags().unwrap();
+    assert!(flags.contains(VmFlags::NORESERVE));
+
+    let memory = AllocOptions::new()
+      .grows_down(true)
+      .alloc(page::size(), Protection::READ_WRITE)?;
+    let flags = query_detailed(memory.as_ptr::<()>())?.vm_flags().unwrap();
+    assert!(flags.contains(VmFlags::GROWSDOWN));
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    target_arch = "x86_64"
+  ))]
+  fn alloc_options_can_allocate_in_low_address_space() -> Result<()> {
+    let memory = AllocOptions::new()
+      .map_32bit(true)
+      .alloc(1, Protection::READ_WRITE)?;
+    assert!(memory.as_range().end <= 1 << 31);
+    Ok(())
+  }
+
+  #[test]
+  fn alloc_options_surround_allocation_with_guard_pages() -> Result<()> {
+    let memory = AllocOptions::new()
+      .guard_pages(1, 2)
+      .alloc(page::size(), Protection::READ_WRITE)?;
+    assert_eq!(memory.len(), page::size());
+
+    let region = crate::query(memory.as_ptr::<()>())?;
+    assert_eq!(region.as_range(), memory.as_range());
+    assert_eq!(region.protection(), Protection::READ_WRITE);
+
+    let start = memory.as_range().start - page::size();
+    let end = memory.as_range().end + page::size() * 2;
+    let regions =
+      crate::query_range(start as *const (), end - start)?.collect::<Result<Vec<_>>>()?;
+
+    assert_eq!(regions.len(), 3);
+    assert_eq!(regions[0].as_range().end, memory.as_range().start);
+    assert_eq!(regions[0].protection(), Protection::NONE);
+    assert_eq!(regions[2].as_range().start, memory.as_range().end);
+    assert_eq!(regions[2].protection(), Protection::NONE);
+    Ok(())
+  }
+
+  #[test]
+  fn alloc_options_free_guard_pages_when_dropped() -> Result<()> {
+    let memory = AllocOptions::new()
+      .guard_pages(1, 1)
+      .alloc(page::size(), Protection::READ_WRITE)?;
+
+    let address = memory.as_ptr::<u8>();
+    let range = (address as usize - page::size())..(address as usize + page::size() * 2);
+    std::mem::drop(memory);
+
+    assert!(crate::query_range(range.start as *const (), range.len())?
+      .next()
+      .is_none());
+    Ok(())
+  }
+
+  #[test]
+  fn alloc_options_reject_overflowing_guard_pages() {
+    assert!(matches!(
+      AllocOptions::new()
+        .guard_pages(usize::MAX, 0)
+        .alloc(1, Protection::READ_WRITE),
+      Err(Error::InvalidParameter(_))
+    ));
+  }
+
+  #[test]
+  fn alloc_options_reject_guard_pages_at_address() -> Result<()> {
+    let neighbor = alloc(page::size(), Protection::READ)?;
+    let address = neighbor.as_range().end;
+
+    assert!(matches!(
+      AllocOptions::new().guard_pages(1, 0).alloc_at(
+        address as *const (),
+        page::size(),
+        Protection::READ_WRITE
+      ),
+      Err(Error::InvalidParameter("guard_pages"))
+    ));
+    assert_eq!(neighbor.query()?.protection(), Protection::READ);
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn alloc_can_be_advised() -> Result<()> {
+    let mut memory = alloc(page::size(), Protection::READ_WRITE)?;
+    memory.advise(Advice::DontFork)?;
+
+    let region = crate::query_detailed(memory.as_ptr::<()>())?;
+    assert!(region
+      .vm_flags()
+      .unwrap()
+      .contains(crate::VmFlags::DONTFORK));
+    Ok(())
+  }
+
+  #[test]
+  fn alloc_rejects_destructive_advice() -> Result<()> {
+    let mut memory = alloc(page::size(), Protection::READ_WRITE)?;
+    memory.as_mut_slice()?[0] = 0xFF;
+
+    for &advice in &[Advice::DontNeed, Advice::Free, Advice::WipeOnFork] {
+      assert!(matches!(
+        memory.advise(advice),
+        Err(Error::InvalidParameter("advice"))
+      ));
+    }
+
+    assert_eq!(memory.as_slice()?[0], 0xFF);
+    Ok(())
+  }
+
+  /// Splits an allocation into two adjacent allocations, at a page offset.
+  unsafe fn split_pages(memory: Allocation, pages: usize) -> (Allocation, Allocation) {
+    let (base, size) = memory.into_raw_parts::<u8>();
+    let offset = page::size() * pages;
+
+    (
+      Allocation::from_raw_parts(base, offset),
+      Allocation::from_raw_parts(base.add(offset), size - offset),
+    )
+  }
+
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn alloc_can_grow_in_place() -> Result<()> {
+    let (mut memory, neighbor) =
+      unsafe { split_pages(alloc(page::size() * 3, Protection::READ_WRITE)?, 1) };
+    let base = memory.as_ptr::<u8>();
+    std::mem::drop(neighbor);
+
+    unsafe { *(base as *mut u8) = 0xFF };
+    memory.resize(page::size() * 3, false)?;
+
+    assert_eq!(memory.as_ptr(), base);
+    assert_eq!(memory.len(), page::size() * 3);
+    assert_eq!(unsafe { *base }, 0xFF);
+    assert_eq!(crate::query(base)?.len(), page::size() * 3);
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn alloc_grows_by_moving_if_neighbor_is_taken() -> Result<()> {
+    let (mut memory, _neighbor) =
+      unsafe { split_pages(alloc(page::size() * 2, Protection::READ_WRITE)?, 1) };
+    let base = memory.as_ptr::<u8>();
+    unsafe { *(base as *mut u8) = 0xFF };
+
+    assert!(matches!(
+      memory.resize(page::size() * 2, false),
+      Err(Error::SystemCall(_))
+    ));
+    assert_eq!(memory.as_ptr(), base);
+    assert_eq!(memory.len(), page::size());
+
+    memory.resize(page::size() * 2, true)?;
+    assert_ne!(memory.as_ptr(), base);
+    assert_eq!(memory.len(), page::size() * 2);
+    assert_eq!(unsafe { *memory.as_ptr::<u8>() }, 0xFF);
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn alloc_cannot_grow_across_multiple_mappings() -> Result<()> {
+    let mut memory = alloc(page::size() * 2, Protection::READ_WRITE)?;
+    memory.protect_range(page::size(), 1, Protection::READ)?;
+    let range = memory.as_range();
+
+    let error = memory.resize(page::size() * 3, true).unwrap_err();
+    assert!(matches!(
+      error,
+      Error::SystemCall(ref error) if error.raw_os_error() == Some(libc::EFAULT)
+    ));
+    assert_eq!(memory.as_range(), range);
+
+    memory.resize(page::size(), false)?;
+    assert_eq!(memory.len(), page::size());
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn alloc_can_shrink() -> Result<()> {
+    let mut memory = alloc(page::size() * 3, Protection::READ_EXECUTE)?;
+    memory.resize(1, false)?;
+
+    assert_eq!(memory.len(), page::size());
+    assert_eq!(crate::query(memory.as_ptr::<()>())?.len(), page::size());
+    assert!(matches!(
+      memory.resize(0, false),
+      Err(Error::InvalidParameter(_))
+    ));
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn alloc_can_be_moved() -> Result<()> {
+    let mut memory = alloc(page::size(), Protection::READ_WRITE)?;
+    let destination = alloc(page::size() * 2, Protection::NONE)?;
+    let (replaced, neighbor) = unsafe { split_pages(destination, 1) };
+    let address = replaced.as_ptr::<u8>();
+
+    unsafe {
+      *memory.as_mut_ptr::<u8>() = 0xFF;
+      assert!(matches!(
+        memory.move_to(address.add(1)),
+        Err(Error::InvalidParameter(_))
+      ));
+
+      // The mapping at the destination is replaced by the allocation
+      std::mem::forget(replaced);
+      memory.move_to(address)?;
+      assert_eq!(*memory.as_ptr::<u8>(), 0xFF);
+    }
+
+    assert_eq!(memory.as_ptr(), address);
+    assert_eq!(crate::query(address)?.protection(), Protection::READ_WRITE);
+    assert_eq!(
+      crate::query(neighbor.as_ptr::<()>())?.protection(),
+      Protection::NONE
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn alloc_can_be_protected_safely() -> Result<()> {
+    let mut memory = alloc(page::size() * 3, Protection::READ)?;
+    memory.protect(Protection::READ_WRITE)?;
+    assert_eq!(memory.query()?.protection(), Protection::READ_WRITE);
+
+    memory.protect_range(page::size() + 1, 1, Protection::NONE)?;
+    let regions = memory.regions()?.collect::<Result<Vec<_>>>()?;
+    assert_eq!(regions.len(), 3);
+    assert_eq!(regions[1].protection(), Protection::NONE);
+    assert_eq!(regions[1].len(), page::size());
+
+    assert!(matches!(
+      memory.protect_range(page::size() * 3, 1, Protection::READ),
+      Err(Error::InvalidParameter(_))
+    ));
+    assert!(matches!(
+      memory.protect_range(page::size() * 2, page::size() + 1, Protection::READ),
+      Err(Error::InvalidParameter(_))
+    ));
+    Ok(())
+  }
+
+  #[test]
+  fn alloc_protect_with_handle_restores_protection() -> Result<()> {
+    let mut memory = alloc(page::size(), Protection::READ)?;
+
+    {
+      let guard = memory.protect_with_handle(Protection::READ_WRITE)?;
+      assert_eq!(guard.query()?.protection(), Protection::READ_WRITE);
+      unsafe { *(guard.as_ptr::<u8>() as *mut u8) = 0xFF };
+    }
+
+    assert_eq!(memory.query()?.protection(), Protection::READ);
+    assert_eq!(unsafe { *memory.as_ptr::<u8>() }, 0xFF);
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn alloc_rejects_protection_beyond_allocation() -> Result<()> {
+    let mut memory = AllocOptions::new()
+      .grows_down(true)
+      .alloc(page::size() * 2, Protection::READ_WRITE)?;
+
+    for flag in [Protection::GROWSDOWN, Protection::GROWSUP].iter() {
+      let protection = Protection::READ | *flag;
+      assert!(matches!(
+        memory.protect(protection),
+        Err(Error::InvalidParameter("protection"))
+      ));
+      assert!(matches!(
+        memory.protect_range(page::size(), 1, protection),
+        Err(Error::InvalidParameter("protection"))
+      ));
+      assert!(matches!(
+        memory.protect_with_handle(protection),
+        Err(Error::InvalidParameter("protection"))
+      ));
+    }
+
+    assert_eq!(memory.query()?.protection(), Protection::READ_WRITE);
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn alloc_flush_is_noop_for_anonymous_memory() -> Result<()> {
+    let memory = alloc(page::size(), Protection::READ_WRITE)?;
+    memory.flush()?;
+    memory.flush_async()
+  }
+
+  #[test]
+  fn alloc_can_be_locked() -> Result<()> {
+    let memory = alloc(page::size(), Protection::READ_WRITE)?;
+    memory.lock()?;
+    memory.unlock()
+  }
+
+  #[test]
+  fn alloc_slice_access_is_checked_against_protection() -> Result<()> {
+    let mut memory = alloc(page::size(), Protection::READ)?;
+    assert_eq!(memory.as_slice()?.len(), page::size());
+    assert!(matches!(
+      memory.as_mut_slice(),
+      Err(Error::InsufficientProtection(_))
+    ));
+
+    memory.protect(Protection::READ_WRITE)?;
+    memory.as_mut_slice()?[0] = 0xFF;
+    assert_eq!(memory.as_slice()?[0], 0xFF);
+
+    {
+      let guard = memory.protect_with_handle(Protection::NONE)?;
+      assert!(matches!(
+        guard.as_slice(),
+        Err(Error::InsufficientProtection(_))
+      ));
+    }
+
+    assert_eq!(memory.as_slice()?[0], 0xFF);
+    Ok(())
+  }
+
+  #[test]
+  fn alloc_slice_access_reflects_protected_range() -> Result<()> {
+    let mut memory = alloc(page::size() * 2, Protection::READ_WRITE)?;
+    memory.protect_range(0, memory.len(), Protection::NONE)?;
+    assert!(matches!(
+      memory.as_mut_slice(),
+      Err(Error::InsufficientProtection(_))
+    ));
+
+    memory.protect_range(0, memory.len(), Protection::READ)?;
+    assert!(memory.as_slice().is_ok());
+    assert!(matches!(
+      memory.as_mut_slice(),
+      Err(Error::InsufficientProtection(_))
+    ));
+    Ok(())
+  }
+
+  #[test]
+  fn alloc_slice_access_queries_unknown_protection() -> Result<()> {
+    let (base, size) = alloc(page::size() * 2, Protection::READ_WRITE)?.into_raw_parts::<u8>();
+    let mut memory = unsafe { Allocation::from_raw_parts(base, size) };
+    assert!(memory.as_mut_slice().is_ok());
+
+    memory.protect_range(page::size(), 1, Protection::READ)?;
+    assert!(memory.as_slice().is_ok());
+    assert!(matches!(
+      memory.as_mut_slice(),
+      Err(Error::InsufficientProtection(_))
+    ));
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn alloc_can_be_split_at_page_boundary() -> Result<()> {
+    let memory = AllocOptions::new()
+      .guard_pages(1, 1)
+      .alloc(page::size() * 3, Protection::READ_WRITE)?;
+    let range = memory.as_range();
+
+    let (error, memory) = memory.split_at(1).err().unwrap();
+    assert!(matches!(error, Error::InvalidParameter(_)));
+    let (error, memory) = memory.split_at(range.len()).err().unwrap();
+    assert!(matches!(error, Error::InvalidParameter(_)));
+    assert_eq!(memory.as_range(), range);
+
+    let (head, tail) = memory.split_at(page::size()).ok().unwrap();
+    assert_eq!(head.as_range(), range.start..range.start + page::size());
+    assert_eq!(tail.as_range(), range.start + page::size()..range.end);
+
+    // Only the tail, and its guard page, are freed
+    let tail_start = tail.as_ptr::<()>();
+    std::mem::drop(tail);
+    assert!(crate::query_range(tail_start, range.len())?
+      .next()
+      .is_none());
+    assert_eq!(head.query()?.protection(), Protection::READ_WRITE);
+    assert_eq!(
+      crate::query((range.start - page::size()) as *const ())?.protection(),
+      Protection::NONE
+    );
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn alloc_split_is_aligned_to_huge_page_size() -> Result<()> {
+    let (base, size) = alloc(page::size() * 4, Protection::READ_WRITE)?.into_raw_parts::<u8>();
+    let mut memory = unsafe { Allocation::from_raw_parts(base, size) };
+    memory.page_size = page::size() * 2;
+
+    let (error, memory) = memory.split_at(page::size()).err().unwrap();
+    assert!(matches!(error, Error::InvalidParameter(_)));
+
+    let (head, tail) = memory.split_at(page::size() * 2).ok().unwrap();
+    assert_eq!(
+      (head.len(), tail.len()),
+      (page::size() * 2, page::size() * 2)
+    );
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn alloc_can_be_merged_when_adjacent() -> Result<()> {
+    let memory = alloc(page::size() * 3, Protection::READ_WRITE)?;
+    let range = memory.as_range();
+
+    let (head, rest) = memory.split_at(page::size()).ok().unwrap();
+    let (middle, tail) = rest.split_at(page::size()).ok().unwrap();
+    let (head, tail) = head.try_merge(tail).err().unwrap();
+
+    let head = middle.try_merge(head).ok().unwrap();
+    let mut memory = tail.try_merge(head).ok().unwrap();
+    assert_eq!(memory.as_range(), range);
+    assert_eq!(memory.as_mut_slice()?.len(), page::size() * 3);
+    Ok(())
+  }
 }
@@ -0,0 +1,345 @@
+use crate::{alloc, page, Allocation, Error, Protection, Result};
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::{align_of, size_of};
+use std::ops::{Deref, DerefMut};
+
+/// A page aligned value, owning the pages it resides within.
+///
+/// The value is stored at the base address of an [`Allocation`], created with
+/// [`Protection::READ_WRITE`], and is dropped alongside it.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> region::Result<()> {
+/// let mut buffer = region::PageBox::new([0u8; 512])?;
+/// buffer[0] = 0xFF;
+///
+/// assert_eq!(buffer.as_ptr() as usize % region::page::size(), 0);
+/// # Ok(())
+/// # }
+/// ```
+pub struct PageBox<T> {
+  allocation: Allocation,
+  _marker: PhantomData<T>,
+}
+
+impl<T> PageBox<T> {
+  /// Allocates one or more pages of memory, and places a value within them.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  /// - If the alignment of `T` exceeds the page size,
+  ///   [`Error::InvalidParameter`] will be returned.
+  #[inline]
+  pub fn new(value: T) -> Result<Self> {
+    let mut allocation = alloc_for::<T>(1)?;
+    unsafe { allocation.as_mut_ptr::<T>().write(value) };
+
+    Ok(Self {
+      allocation,
+      _marker: PhantomData,
+    })
+  }
+
+  /// Returns a pointer to the value.
+  ///
+  /// The address is always aligned to the operating system's page size.
+  #[inline(always)]
+  pub fn as_ptr(&self) -> *const T {
+    self.allocation.as_ptr()
+  }
+}
+
+impl<T> Deref for PageBox<T> {
+  type Target = T;
+
+  #[inline]
+  fn deref(&self) -> &T {
+    unsafe { &*self.as_ptr() }
+  }
+}
+
+impl<T> DerefMut for PageBox<T> {
+  #[inline]
+  fn deref_mut(&mut self) -> &mut T {
+    unsafe { &mut *self.allocation.as_mut_ptr() }
+  }
+}
+
+impl<T> Drop for PageBox<T> {
+  #[inline]
+  fn drop(&mut self) {
+    unsafe { std::ptr::drop_in_place(self.allocation.as_mut_ptr::<T>()) };
+  }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PageBox<T> {
+  #[inline]
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Debug::fmt(&**self, f)
+  }
+}
+
+unsafe impl<T: Send> Send for PageBox<T> {}
+unsafe impl<T: Sync> Sync for PageBox<T> {}
+
+/// A page aligned, growable, array of values, owning the pages it resides
+/// within.
+///
+/// The values are stored in an [`Allocation`], created with
+/// [`Protection::READ_WRITE`]. When the capacity is exceeded, the allocation
+/// is grown using [`Allocation::resize`] where supported (avoiding a copy on
+/// Linux), and reallocated otherwise.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> region::Result<()> {
+/// let mut values = region::PageVec::with_capacity(4)?;
+/// values.push(1u64)?;
+/// values.push(2)?;
+///
+/// assert_eq!(&values[..], &[1, 2]);
+/// assert_eq!(values.as_ptr() as usize % region::page::size(), 0);
+/// # Ok(())
+/// # }
+/// ```
+pub struct PageVec<T> {
+  allocation: Allocation,
+  len: usize,
+  _marker: PhantomData<T>,
+}
+
+impl<T> PageVec<T> {
+  /// Creates an empty array, with capacity for at least `capacity` values.
+  ///
+  /// The capacity is rounded up to fill the allocation's pages, thus at least
+  /// one page is always allocated.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  /// - If the capacity overflows the address space, or the alignment of `T`
+  ///   exceeds the page size, [`Error::InvalidParameter`] will be returned.
+  #[inline]
+  pub fn with_capacity(capacity: usize) -> Result<Self> {
+    Ok(Self {
+      allocation: alloc_for::<T>(capacity)?,
+      len: 0,
+      _marker: PhantomData,
+    })
+  }
+
+  /// Returns the number of values in the array.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  /// Returns whether the array is empty.
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns the number of values the array can hold, without growing.
+  #[inline]
+  pub fn capacity(&self) -> usize {
+    match size_of::<T>() {
+      0 => usize::MAX,
+      size => self.allocation.len() / size,
+    }
+  }
+
+  /// Returns a pointer to the array's first value.
+  ///
+  /// The address is always aligned to the operating system's page size.
+  #[inline(always)]
+  pub fn as_ptr(&self) -> *const T {
+    self.allocation.as_ptr()
+  }
+
+  /// Appends a value to the back of the array.
+  ///
+  /// # Errors
+  ///
+  /// - If the array must grow, and an interaction with the underlying
+  ///   operating system fails, an error will be returned.
+  #[inline]
+  pub fn push(&mut self, value: T) -> Result<()> {
+    if self.len == self.capacity() {
+      self.grow(self.len.saturating_mul(2).max(self.len + 1))?;
+    }
+
+    unsafe { self.allocation.as_mut_ptr::<T>().add(self.len).write(value) };
+    self.len += 1;
+    Ok(())
+  }
+
+  /// Removes the last value from the array, and returns it.
+  #[inline]
+  pub fn pop(&mut self) -> Option<T> {
+    if self.len == 0 {
+      return None;
+    }
+
+    self.len -= 1;
+    Some(unsafe { self.as_ptr().add(self.len).read() })
+  }
+
+  /// Removes all values from the array, retaining its capacity.
+  #[inline]
+  pub fn clear(&mut self) {
+    let values: *mut [T] = &mut **self;
+    self.len = 0;
+    unsafe { std::ptr::drop_in_place(values) };
+  }
+
+  /// Grows the array to have capacity for at least `capacity` values.
+  fn grow(&mut self, capacity: usize) -> Result<()> {
+    let size = capacity
+      .checked_mul(size_of::<T>())
+      .ok_or(Error::InvalidParameter("capacity"))?;
+
+    if self.allocation.resize(size, true).is_err() {
+      let mut allocation = alloc_for::<T>(capacity)?;
+
+      unsafe {
+        let values = allocation.as_mut_ptr::<T>();
+        values.copy_from_nonoverlapping(self.as_ptr(), self.len);
+      }
+
+      self.allocation = allocation;
+    }
+
+    Ok(())
+  }
+}
+
+impl<T> Deref for PageVec<T> {
+  type Target = [T];
+
+  #[inline]
+  fn deref(&self) -> &[T] {
+    unsafe { std::slice::from_raw_parts(self.as_ptr(), self.len) }
+  }
+}
+
+impl<T> DerefMut for PageVec<T> {
+  #[inline]
+  fn deref_mut(&mut self) -> &mut [T] {
+    let len = self.len;
+    unsafe { std::slice::from_raw_parts_mut(self.allocation.as_mut_ptr(), len) }
+  }
+}
+
+impl<T> Drop for PageVec<T> {
+  #[inline]
+  fn drop(&mut self) {
+    self.clear();
+  }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PageVec<T> {
+  #[inline]
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Debug::fmt(&**self, f)
+  }
+}
+
+unsafe impl<T: Send> Send for PageVec<T> {}
+unsafe impl<T: Sync> Sync for PageVec<T> {}
+
+/// Allocates readable and writable memory for a number of values.
+fn alloc_for<T>(count: usize) -> Result<Allocation> {
+  if align_of::<T>() > page::size() {
+    return Err(Error::InvalidParameter("alignment"));
+  }
+
+  let size = count
+    .checked_mul(size_of::<T>())
+    .ok_or(Error::InvalidParameter("capacity"))?;
+
+  alloc(size.max(1), Protection::READ_WRITE)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::rc::Rc;
+
+  #[test]
+  fn page_box_is_page_aligned() -> Result<()> {
+    let mut value = PageBox::new(5u32)?;
+    *value += 1;
+
+    assert_eq!(*value, 6);
+    assert_eq!(value.as_ptr() as usize % page::size(), 0);
+    assert_eq!(
+      crate::query(value.as_ptr())?.protection(),
+      Protection::READ_WRITE
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn page_box_drops_value() -> Result<()> {
+    let value = Rc::new(());
+    std::mem::drop(PageBox::new(value.clone())?);
+    assert_eq!(Rc::strong_count(&value), 1);
+    Ok(())
+  }
+
+  #[test]
+  fn page_vec_grows_beyond_capacity() -> Result<()> {
+    let mut values = PageVec::with_capacity(1)?;
+    let capacity = values.capacity();
+    assert_eq!(capacity, page::size() / size_of::<u64>());
+
+    for value in 0..(capacity as u64 * 3) {
+      values.push(value)?;
+    }
+
+    assert!(values.capacity() >= capacity * 3);
+    assert_eq!(values.len(), capacity * 3);
+    assert!(values
+      .iter()
+      .enumerate()
+      .all(|(index, value)| *value == index as u64));
+
+    assert_eq!(values.pop(), Some(capacity as u64 * 3 - 1));
+    Ok(())
+  }
+
+  #[test]
+  fn page_vec_drops_values() -> Result<()> {
+    let value = Rc::new(());
+    let mut values = PageVec::with_capacity(2)?;
+    values.push(value.clone())?;
+    values.push(value.clone())?;
+    assert_eq!(Rc::strong_count(&value), 3);
+
+    values.clear();
+    assert!(values.is_empty());
+    assert_eq!(Rc::strong_count(&value), 1);
+
+    values.push(value.clone())?;
+    std::mem::drop(values);
+    assert_eq!(Rc::strong_count(&value), 1);
+    Ok(())
+  }
+
+  #[test]
+  fn page_vec_supports_zero_sized_values() -> Result<()> {
+    let mut values = PageVec::with_capacity(0)?;
+    values.push(())?;
+    assert_eq!(values.len(), 1);
+    assert_eq!(values.capacity(), usize::MAX);
+    Ok(())
+  }
+}
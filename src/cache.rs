@@ -0,0 +1,106 @@
+use crate::{os, Error, Result};
+
+/// Flushes the instruction cache for a range of memory.
+///
+/// This must be called after code has been written to memory (e.g. by a JIT
+/// compiler, or when patching code in place), before it's executed. Otherwise
+/// the CPU may execute stale instructions on architectures where the
+/// instruction cache is not coherent with the data cache (e.g. AArch64).
+///
+/// The flush only applies to the calling thread's core on some architectures.
+/// Other threads executing the code must also issue an instruction
+/// synchronization barrier (or be synchronized by other means).
+///
+/// # Parameters
+///
+/// - The range is `[address, address + size)`
+/// - The size may not be zero.
+///
+/// # Errors
+///
+/// - If an interaction with the underlying operating system fails, an error
+///   will be returned.
+/// - If size is zero, [`Error::InvalidParameter`] will be returned.
+///
+/// # Safety
+///
+/// The range must be mapped memory. On some architectures (e.g. AArch64), the
+/// cache is flushed by instructions which fault on unmapped addresses.
+///
+/// # Implementation
+///
+/// This function is implemented using `FlushInstructionCache` on Windows,
+/// `sys_icache_invalidate` on macOS & iOS, and `__clear_cache` elsewhere. It's
+/// a no-op on other x86 systems, since their instruction cache is coherent.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> region::Result<()> {
+/// use region::Protection;
+///
+/// let memory = region::alloc(100, Protection::READ_WRITE)?;
+/// // ...write code to memory...
+/// unsafe { region::flush_instruction_cache(memory.as_ptr::<u8>(), memory.len())? };
+/// # Ok(())
+/// # }
+/// ```
+#[inline]
+pub unsafe fn flush_instruction_cache<T>(address: *const T, size: usize) -> Result<()> {
+  if size == 0 {
+    return Err(Error::InvalidParameter("size"));
+  }
+
+  os::flush_instruction_cache(address.cast(), size)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{alloc, Protection};
+
+  #[test]
+  fn flush_instruction_cache_rejects_empty_range() {
+    assert!(matches!(
+      unsafe { flush_instruction_cache(std::ptr::null::<()>(), 0) },
+      Err(Error::InvalidParameter(_))
+    ));
+  }
+
+  #[test]
+  #[cfg(all(
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+    not(any(
+      target_os = "openbsd",
+      target_os = "netbsd",
+      all(target_vendor = "apple", target_arch = "aarch64")
+    ))
+  ))]
+  fn flush_instruction_cache_allows_rewriting_code() -> Result<()> {
+    #[cfg(target_arch = "x86_64")]
+    let (ret5, patch) = ([0xB8, 0x05, 0x00, 0x00, 0x00, 0xC3u8], (1, 0x07));
+    #[cfg(target_arch = "aarch64")]
+    let (ret5, patch) = (
+      [0xA0, 0x00, 0x80, 0x52, 0xC0, 0x03, 0x5F, 0xD6u8],
+      (0, 0xE0),
+    );
+
+    let memory = alloc(ret5.len(), Protection::READ_WRITE_EXECUTE)?;
+    let code = memory.as_ptr::<u8>() as *mut u8;
+    let x: extern "C" fn() -> i32 = unsafe { std::mem::transmute(code) };
+
+    unsafe {
+      code.copy_from_nonoverlapping(ret5.as_ptr(), ret5.len());
+      flush_instruction_cache(code, ret5.len())?;
+    }
+    assert_eq!(x(), 5);
+
+    // Patch the immediate operand of the instruction
+    unsafe {
+      *code.add(patch.0) = patch.1;
+      flush_instruction_cache(code, ret5.len())?;
+    }
+    assert_eq!(x(), 7);
+    Ok(())
+  }
+}
@@ -0,0 +1,177 @@
+use crate::{map_file, os, page, Allocation, Error, Protection, Result};
+use std::ffi::CStr;
+
+/// A handle to memory that is mapped twice: once writable, once executable.
+///
+/// Both views are backed by the same physical pages, thus any code written to
+/// the writable view is immediately visible in the executable view. No single
+/// view is ever both writable and executable (i.e. W^X), which is required on
+/// hardened systems (e.g. SELinux's `execmem`, or PaX's `MPROTECT`), where
+/// allocating `READ_WRITE_EXECUTE` memory is rejected.
+///
+/// Both views are freed once the handle is dropped.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> region::Result<()> {
+/// # if cfg!(target_arch = "x86_64") {
+/// let ret5 = [0xB8, 0x05, 0x00, 0x00, 0x00, 0xC3u8];
+///
+/// let memory = region::alloc_dual(ret5.len())?;
+/// unsafe {
+///   let code = memory.writable().as_ptr::<u8>() as *mut u8;
+///   code.copy_from_nonoverlapping(ret5.as_ptr(), ret5.len());
+/// }
+///
+/// let code = memory.executable().as_ptr::<u8>();
+/// let x: extern "C" fn() -> i32 = unsafe { std::mem::transmute(code) };
+/// assert_eq!(x(), 5);
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+pub struct DualAllocation {
+  writable: Allocation,
+  executable: Allocation,
+}
+
+impl DualAllocation {
+  /// Returns the writable view, with [`Protection::READ_WRITE`].
+  #[inline(always)]
+  pub fn writable(&self) -> &Allocation {
+    &self.writable
+  }
+
+  /// Returns the executable view, with [`Protection::READ_EXECUTE`].
+  #[inline(always)]
+  pub fn executable(&self) -> &Allocation {
+    &self.executable
+  }
+
+  /// Translates an address within the writable view, to its counterpart in
+  /// the executable view.
+  ///
+  /// # Panics
+  ///
+  /// This function panics if the address is not within the writable view.
+  #[inline]
+  pub fn to_executable<T>(&self, address: *const T) -> *const T {
+    let range = self.writable.as_range();
+    assert!(
+      range.contains(&(address as usize)),
+      "address is not within the writable view"
+    );
+
+    (self.executable.as_range().start + (address as usize - range.start)) as *const T
+  }
+
+  /// Returns the size of each view in bytes.
+  ///
+  /// The size is always aligned to a multiple of the operating system's page
+  /// size.
+  #[allow(clippy::len_without_is_empty)]
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.writable.len()
+  }
+}
+
+/// Allocates one or more pages of memory, mapped both writable and executable.
+///
+/// See [`DualAllocation`] for more information.
+///
+/// # Parameters
+///
+/// - The size may not be zero.
+/// - The size is rounded up to the closest page boundary.
+///
+/// # Errors
+///
+/// - If an interaction with the underlying operating system fails, an error
+///   will be returned.
+/// - If size is zero, [`Error::InvalidParameter`] will be returned.
+///
+/// # Implementation
+///
+/// This function is implemented using an anonymous file (created using
+/// `memfd_create`), which is mapped twice using `mmap` with `MAP_SHARED`.
+#[inline]
+pub fn alloc_dual(size: usize) -> Result<DualAllocation> {
+  if size == 0 {
+    return Err(Error::InvalidParameter("size"));
+  }
+
+  let size = page::ceil(size as *const ()) as usize;
+  let name = CStr::from_bytes_with_nul(b"region-dual\0").expect("valid name");
+
+  // The file is only required for creating the views, which retain its pages
+  let file = os::memfd_create(name)?;
+  file.set_len(size as u64).map_err(Error::SystemCall)?;
+
+  // The file is anonymous, thus it is only ever accessed through the views
+  unsafe {
+    Ok(DualAllocation {
+      writable: map_file(&file, 0, size, Protection::READ_WRITE, true)?,
+      executable: map_file(&file, 0, size, Protection::READ_EXECUTE, true)?,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn alloc_dual_rejects_empty_allocation() {
+    assert!(matches!(alloc_dual(0), Err(Error::InvalidParameter(_))));
+  }
+
+  #[test]
+  fn alloc_dual_views_share_memory() -> Result<()> {
+    let memory = alloc_dual(1)?;
+    assert_eq!(memory.len(), page::size());
+    assert_ne!(
+      memory.writable().as_ptr::<u8>(),
+      memory.executable().as_ptr()
+    );
+
+    let data = unsafe { memory.writable().as_ptr::<u8>().add(10) };
+    unsafe { *(data as *mut u8) = 0xFF };
+
+    let code = memory.to_executable(data);
+    assert_eq!(code, unsafe { memory.executable().as_ptr::<u8>().add(10) });
+    assert_eq!(unsafe { *code }, 0xFF);
+    Ok(())
+  }
+
+  #[test]
+  fn alloc_dual_views_have_distinct_protection() -> Result<()> {
+    let memory = alloc_dual(page::size() * 2)?;
+
+    let writable = crate::query(memory.writable().as_ptr::<()>())?;
+    assert_eq!(writable.protection(), Protection::READ_WRITE);
+    assert!(writable.is_shared());
+
+    let executable = crate::query(memory.executable().as_ptr::<()>())?;
+    assert_eq!(executable.protection(), Protection::READ_EXECUTE);
+    assert!(executable.is_shared());
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(target_arch = "x86_64")]
+  fn alloc_dual_can_execute_written_code() -> Result<()> {
+    let ret5 = [0xB8, 0x05, 0x00, 0x00, 0x00, 0xC3u8];
+    let memory = alloc_dual(ret5.len())?;
+
+    unsafe {
+      let code = memory.writable().as_ptr::<u8>() as *mut u8;
+      code.copy_from_nonoverlapping(ret5.as_ptr(), ret5.len());
+
+      let x: extern "C" fn() -> i32 = std::mem::transmute(memory.executable().as_ptr::<u8>());
+      assert_eq!(x(), 5);
+    }
+    Ok(())
+  }
+}
@@ -1,5 +1,6 @@
 //! Error types and utilities.
 
+use crate::Protection;
 use std::error::Error as StdError;
 use std::{fmt, io};
 
@@ -8,6 +9,7 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 /// A collection of possible errors.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
   /// The queried memory is unmapped.
   ///
@@ -17,6 +19,16 @@ pub enum Error {
   UnmappedRegion,
   /// A supplied parameter is invalid.
   InvalidParameter(&'static str),
+  /// An operation, or a supplied parameter, is not supported by the OS.
+  Unsupported(&'static str),
+  /// The memory's protection does not provide the required access.
+  InsufficientProtection(Protection),
+  /// The requested protection exceeds the memory's maximum protection.
+  ///
+  /// This contains the maximum protection (see [`Region::max_protection`]).
+  ///
+  /// [`Region::max_protection`]: crate::Region::max_protection
+  MaxProtectionExceeded(Protection),
   /// A procfs region could not be parsed.
   ProcfsInput(String),
   /// A system call failed.
@@ -31,6 +43,21 @@ impl fmt::Display for Error {
     match self {
       Error::UnmappedRegion => write!(f, "Queried memory is unmapped"),
       Error::InvalidParameter(param) => write!(f, "Invalid parameter value: {}", param),
+      Error::Unsupported(param) => write!(f, "Unsupported by the OS: {}", param),
+      Error::InsufficientProtection(protection) => {
+        write!(
+          f,
+          "Insufficient memory protection, requires: {:?}",
+          protection
+        )
+      }
+      Error::MaxProtectionExceeded(protection) => {
+        write!(
+          f,
+          "Protection exceeds the maximum protection: {:?}",
+          protection
+        )
+      }
       Error::ProcfsInput(ref input) => write!(f, "Invalid procfs input: {}", input),
       Error::SystemCall(ref error) => write!(f, "System call failed: {}", error),
       Error::MachCall(code) => write!(f, "macOS kernel call failed: {}", code),
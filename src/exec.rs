@@ -0,0 +1,264 @@
+use crate::{alloc, flush_instruction_cache, os, Allocation, Protection, Result};
+use std::mem::size_of;
+use std::ops::{Deref, DerefMut};
+
+/// A writable buffer, used for emitting code that's later executed.
+///
+/// The buffer is created with [`Protection::READ_WRITE`], and dereferences to
+/// a byte slice whilst code is emitted. Once the code is complete,
+/// [`ExecutableBuffer::finalize`] seals the buffer, returning an
+/// [`ExecutableCode`] handle which can provide function pointers.
+///
+/// No page of the buffer is ever both writable and executable at once.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> region::Result<()> {
+/// # if cfg!(any(target_arch = "x86", target_arch = "x86_64"))
+/// #   && !cfg!(any(target_os = "openbsd", target_os = "netbsd")) {
+/// let mut buffer = region::ExecutableBuffer::new(6)?;
+/// buffer[..6].copy_from_slice(&[0xB8, 0x05, 0x00, 0x00, 0x00, 0xC3]);
+///
+/// let code = buffer.finalize()?;
+/// let x: extern "C" fn() -> i32 = unsafe { code.as_fn(0) };
+/// assert_eq!(x(), 5);
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+pub struct ExecutableBuffer {
+  allocation: Allocation,
+}
+
+impl ExecutableBuffer {
+  /// Allocates a writable buffer for emitting code.
+  ///
+  /// # Parameters
+  ///
+  /// - The size may not be zero.
+  /// - The size is rounded up to the closest page boundary.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  /// - If size is zero,
+  ///   [`Error::InvalidParameter`](crate::Error::InvalidParameter) will be
+  ///   returned.
+  #[inline]
+  pub fn new(size: usize) -> Result<Self> {
+    alloc(size, Protection::READ_WRITE).map(|allocation| Self { allocation })
+  }
+
+  /// Seals the buffer, making it executable.
+  ///
+  /// The buffer's protection is changed to [`Protection::READ_EXECUTE`], and
+  /// the instruction cache is flushed.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  #[inline]
+  pub fn finalize(self) -> Result<ExecutableCode> {
+    let code = ExecutableCode {
+      allocation: self.allocation,
+    };
+
+    code.seal()?;
+    Ok(code)
+  }
+}
+
+impl Deref for ExecutableBuffer {
+  type Target = [u8];
+
+  #[inline]
+  fn deref(&self) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(self.allocation.as_ptr(), self.allocation.len()) }
+  }
+}
+
+impl DerefMut for ExecutableBuffer {
+  #[inline]
+  fn deref_mut(&mut self) -> &mut [u8] {
+    let len = self.allocation.len();
+    unsafe { std::slice::from_raw_parts_mut(self.allocation.as_mut_ptr(), len) }
+  }
+}
+
+/// A handle to sealed, executable, code.
+///
+/// This `struct` is created by [`ExecutableBuffer::finalize`]. See its
+/// documentation for more.
+pub struct ExecutableCode {
+  allocation: Allocation,
+}
+
+impl ExecutableCode {
+  /// Returns a pointer to the code's base address.
+  #[inline(always)]
+  pub fn as_ptr(&self) -> *const u8 {
+    self.allocation.as_ptr()
+  }
+
+  /// Returns the size of the code buffer in bytes.
+  #[allow(clippy::len_without_is_empty)]
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.allocation.len()
+  }
+
+  /// Returns a function pointer to the code at an offset.
+  ///
+  /// # Panics
+  ///
+  /// This function panics if the offset is outside of the buffer, or if `F`
+  /// is not pointer-sized.
+  ///
+  /// # Safety
+  ///
+  /// The type `F` must be a function pointer, with a signature (and calling
+  /// convention) matching the code at the offset. The function pointer must
+  /// not be called after the handle is dropped, or whilst the code is
+  /// writable.
+  #[inline]
+  pub unsafe fn as_fn<F: Copy>(&self, offset: usize) -> F {
+    assert!(offset < self.len(), "offset is outside of the buffer");
+    assert_eq!(size_of::<F>(), size_of::<*const u8>(), "F is not a pointer");
+
+    let address = self.as_ptr().add(offset);
+    std::mem::transmute_copy(&address)
+  }
+
+  /// Temporarily makes the code writable, for patching it.
+  ///
+  /// The returned guard dereferences to the code as a byte slice. Once the
+  /// guard is dropped, the code is sealed again (as with
+  /// [`ExecutableBuffer::finalize`]).
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  #[inline]
+  pub fn make_writable(&mut self) -> Result<ExecutableWriteGuard<'_>> {
+    unsafe { os::protect(self.allocation.as_ptr(), self.len(), Protection::READ_WRITE)? };
+    Ok(ExecutableWriteGuard { code: self })
+  }
+
+  /// Changes the protection to executable, and flushes the instruction cache.
+  fn seal(&self) -> Result<()> {
+    let (address, size) = (self.allocation.as_ptr(), self.len());
+
+    unsafe {
+      os::protect(address, size, Protection::READ_EXECUTE)?;
+      flush_instruction_cache(address, size)
+    }
+  }
+}
+
+/// A RAII implementation of a scoped writable code guard.
+///
+/// When this structure is dropped (falls out of scope), the code is sealed
+/// again, and the instruction cache is flushed.
+///
+/// This `struct` is created by [`ExecutableCode::make_writable`].
+#[must_use]
+pub struct ExecutableWriteGuard<'a> {
+  code: &'a mut ExecutableCode,
+}
+
+impl Deref for ExecutableWriteGuard<'_> {
+  type Target = [u8];
+
+  #[inline]
+  fn deref(&self) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(self.code.as_ptr(), self.code.len()) }
+  }
+}
+
+impl DerefMut for ExecutableWriteGuard<'_> {
+  #[inline]
+  fn deref_mut(&mut self) -> &mut [u8] {
+    let len = self.code.len();
+    unsafe { std::slice::from_raw_parts_mut(self.code.allocation.as_mut_ptr(), len) }
+  }
+}
+
+impl Drop for ExecutableWriteGuard<'_> {
+  #[inline]
+  fn drop(&mut self) {
+    let result = self.code.seal();
+    debug_assert!(result.is_ok(), "sealing code: {:?}", result);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::page;
+
+  #[test]
+  fn executable_buffer_is_writable_until_finalized() -> Result<()> {
+    let mut buffer = ExecutableBuffer::new(1)?;
+    assert_eq!(buffer.len(), page::size());
+    buffer[0] = 0xC3;
+
+    let region = crate::query(buffer.as_ptr())?;
+    assert_eq!(region.protection(), Protection::READ_WRITE);
+
+    let code = buffer.finalize()?;
+    let region = crate::query(code.as_ptr())?;
+    assert_eq!(region.protection(), Protection::READ_EXECUTE);
+    assert_eq!(unsafe { *code.as_ptr() }, 0xC3);
+    Ok(())
+  }
+
+  #[test]
+  fn executable_code_can_be_made_writable() -> Result<()> {
+    let mut code = ExecutableBuffer::new(1)?.finalize()?;
+
+    {
+      let mut guard = code.make_writable()?;
+      guard[0] = 0xC3;
+      assert_eq!(
+        crate::query(guard.as_ptr())?.protection(),
+        Protection::READ_WRITE
+      );
+    }
+
+    assert_eq!(
+      crate::query(code.as_ptr())?.protection(),
+      Protection::READ_EXECUTE
+    );
+    assert_eq!(unsafe { *code.as_ptr() }, 0xC3);
+    Ok(())
+  }
+
+  #[test]
+  #[should_panic]
+  fn executable_code_rejects_out_of_bounds_fn() {
+    let code = ExecutableBuffer::new(1).unwrap().finalize().unwrap();
+    let _: fn() = unsafe { code.as_fn(page::size()) };
+  }
+
+  #[test]
+  #[cfg(all(
+    target_arch = "x86_64",
+    not(any(target_os = "openbsd", target_os = "netbsd"))
+  ))]
+  fn executable_code_can_be_patched() -> Result<()> {
+    let mut buffer = ExecutableBuffer::new(6)?;
+    buffer[..6].copy_from_slice(&[0xB8, 0x05, 0x00, 0x00, 0x00, 0xC3]);
+
+    let mut code = buffer.finalize()?;
+    let x: extern "C" fn() -> i32 = unsafe { code.as_fn(0) };
+    assert_eq!(x(), 5);
+
+    code.make_writable()?[1] = 0x07;
+    assert_eq!(x(), 7);
+    Ok(())
+  }
+}
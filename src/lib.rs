@@ -82,26 +82,50 @@
 #[macro_use]
 extern crate bitflags;
 
-pub use alloc::{alloc, alloc_at, Allocation};
+pub use advise::{advise, Advice};
+pub use alloc::{alloc, alloc_at, AllocOptions, Allocation, AllocationProtectGuard};
+pub use boxed::{PageBox, PageVec};
+pub use cache::flush_instruction_cache;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use dual::{alloc_dual, DualAllocation};
 pub use error::{Error, Result};
+pub use exec::{ExecutableBuffer, ExecutableCode, ExecutableWriteGuard};
 pub use lock::{lock, unlock, LockGuard};
+pub use map::map_file;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use process::Process;
 pub use protect::{protect, protect_with_handle, ProtectGuard};
-pub use query::{query, query_range, QueryIter};
+pub use query::{find_free, query, query_range, QueryIter};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use query::{query_detailed, query_range_detailed, query_usage};
+pub use reserve::{reserve, Reservation};
+pub use snapshot::{snapshot, MemoryMap, RegionChange};
 
+mod advise;
 mod alloc;
+mod boxed;
+mod cache;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod dual;
 mod error;
+mod exec;
 mod lock;
+mod map;
 mod os;
 pub mod page;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod process;
 mod protect;
 mod query;
+mod reserve;
+mod snapshot;
 mod util;
 
 /// A descriptor for a mapped memory region.
 ///
 /// The region encompasses zero or more pages (e.g. OpenBSD can have null-sized
 /// virtual pages).
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Region {
   /// Base address of the region
   base: *const (),
@@ -111,12 +135,20 @@ pub struct Region {
   guarded: bool,
   /// Protection of the region
   protection: Protection,
-  /// Maximum protection of the region
-  max_protection: Protection,
+  /// Maximum protection of the region, if known
+  max_protection: Option<Protection>,
   /// Whether the region is shared or not
   shared: bool,
   /// Size of the region (multiple of page size)
   size: usize,
+  /// The file backing the region, if any
+  file: Option<MappedFile>,
+  /// The pseudo path of the region, if any
+  name: Option<String>,
+  /// Memory usage of the region, if queried
+  usage: Option<MemoryUsage>,
+  /// Kernel flags of the region, if queried
+  vm_flags: Option<VmFlags>,
 }
 
 impl Region {
@@ -162,7 +194,8 @@ impl Region {
   /// Returns whether the region is committed or not.
   ///
   /// This is always true for all operating system's, the exception being
-  /// `MEM_RESERVE` pages on Windows.
+  /// `MEM_RESERVE` pages on Windows, and the uncommitted pages of a
+  /// [`Reservation`] on other systems.
   #[inline(always)]
   pub fn is_committed(&self) -> bool {
     !self.reserved
@@ -218,6 +251,66 @@ impl Region {
   pub fn protection(&self) -> Protection {
     self.protection
   }
+
+  /// Returns the maximum protection of the region, if known.
+  ///
+  /// The region's protection cannot be changed beyond its maximum (e.g. a
+  /// shared mapping of a file opened as read-only can never become writable).
+  ///
+  /// # OS-Specific Behavior
+  ///
+  /// This is available on macOS, NetBSD & OpenBSD, and on Linux for regions
+  /// obtained using [`query_detailed`] or [`query_range_detailed`]. For all
+  /// other regions `None` is returned.
+  #[inline(always)]
+  pub fn max_protection(&self) -> Option<Protection> {
+    self.max_protection
+  }
+
+  /// Returns the file backing the region, if any.
+  ///
+  /// # OS-Specific Behavior
+  ///
+  /// This is only available on Linux, for all other platforms `None` is
+  /// returned.
+  #[inline(always)]
+  pub fn file(&self) -> Option<&MappedFile> {
+    self.file.as_ref()
+  }
+
+  /// Returns the pseudo path of a region that is not backed by a file.
+  ///
+  /// These are names assigned by the kernel (e.g. `[heap]`, `[stack]` or
+  /// `[vdso]`), or by the user (e.g. `[anon:<name>]`).
+  ///
+  /// # OS-Specific Behavior
+  ///
+  /// This is only available on Linux, for all other platforms `None` is
+  /// returned.
+  #[inline(always)]
+  pub fn name(&self) -> Option<&str> {
+    self.name.as_deref()
+  }
+
+  /// Returns the memory usage of the region.
+  ///
+  /// This is only available for regions obtained using
+  /// [`query_detailed`] or [`query_range_detailed`], for all other regions
+  /// `None` is returned.
+  #[inline(always)]
+  pub fn usage(&self) -> Option<MemoryUsage> {
+    self.usage
+  }
+
+  /// Returns the kernel flags of the region.
+  ///
+  /// This is only available for regions obtained using
+  /// [`query_detailed`] or [`query_range_detailed`], for all other regions
+  /// `None` is returned.
+  #[inline(always)]
+  pub fn vm_flags(&self) -> Option<VmFlags> {
+    self.vm_flags
+  }
 }
 
 impl Default for Region {
@@ -228,9 +321,58 @@ impl Default for Region {
       reserved: false,
       guarded: false,
       protection: Protection::NONE,
-      max_protection: Protection::NONE,
+      max_protection: None,
       shared: false,
       size: 0,
+      file: None,
+      name: None,
+      usage: None,
+      vm_flags: None,
+    }
+  }
+}
+
+/// The region is displayed as a line of `/proc/[pid]/maps`, i.e. its address
+/// range, protection, sharing mode, file offset, device, inode and path (e.g.
+/// `00400000-00452000 r-xp 00000000 08:02 173521 /usr/bin/dbus-daemon`).
+///
+/// The alternate format (`{:#}`) is intended for humans, replacing the file's
+/// offset, device & inode with the region's size (e.g. `0x400000-0x452000
+/// r-xp 328 KiB /usr/bin/dbus-daemon`).
+impl std::fmt::Display for Region {
+  #[inline]
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    let range = self.as_range();
+    let protection = self.protection & Protection::READ_WRITE_EXECUTE;
+    let sharing = if self.shared { 's' } else { 'p' };
+
+    if f.alternate() {
+      write!(
+        f,
+        "{:#x}-{:#x} {}{} {}",
+        range.start,
+        range.end,
+        protection,
+        sharing,
+        ByteSize(self.size)
+      )?;
+    } else {
+      let (offset, device, inode) = self.file.as_ref().map_or((0, (0, 0), 0), |file| {
+        (file.offset, file.device_numbers(), file.inode)
+      });
+
+      write!(
+        f,
+        "{:08x}-{:08x} {}{} {:08x} {:02x}:{:02x} {}",
+        range.start, range.end, protection, sharing, offset, device.0, device.1, inode
+      )?;
+    }
+
+    match (&self.file, &self.name) {
+      (Some(file), _) if file.deleted => write!(f, " {} (deleted)", file.path.display()),
+      (Some(file), _) => write!(f, " {}", file.path.display()),
+      (None, Some(name)) => write!(f, " {}", name),
+      (None, None) => Ok(()),
     }
   }
 }
@@ -238,6 +380,175 @@ impl Default for Region {
 unsafe impl Send for Region {}
 unsafe impl Sync for Region {}
 
+/// A descriptor for the file backing a mapped memory region.
+///
+/// This `struct` is obtained using [`Region::file`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappedFile {
+  /// Path of the file
+  path: std::path::PathBuf,
+  /// Offset of the mapping within the file
+  offset: u64,
+  /// Device containing the file
+  device: u64,
+  /// Inode of the file
+  inode: u64,
+  /// Whether the file has been deleted or not
+  deleted: bool,
+}
+
+impl MappedFile {
+  /// Returns the path of the file.
+  ///
+  /// If the file has been deleted, the path it had before it was unlinked is
+  /// returned (see [`MappedFile::is_deleted`]).
+  #[inline(always)]
+  pub fn path(&self) -> &std::path::Path {
+    &self.path
+  }
+
+  /// Returns the offset, in bytes, of the region's base address within the
+  /// file.
+  #[inline(always)]
+  pub fn offset(&self) -> u64 {
+    self.offset
+  }
+
+  /// Returns the ID of the device containing the file.
+  ///
+  /// The value is comparable to `std::os::unix::fs::MetadataExt::dev`.
+  #[inline(always)]
+  pub fn device(&self) -> u64 {
+    self.device
+  }
+
+  /// Returns the inode of the file.
+  #[inline(always)]
+  pub fn inode(&self) -> u64 {
+    self.inode
+  }
+
+  /// Returns whether the file has been deleted since it was mapped.
+  #[inline(always)]
+  pub fn is_deleted(&self) -> bool {
+    self.deleted
+  }
+
+  /// Returns the major and minor numbers of the device containing the file.
+  fn device_numbers(&self) -> (u64, u64) {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+      let device = self.device as libc::dev_t;
+      (libc::major(device) as u64, libc::minor(device) as u64)
+    }
+
+    // Files backing regions are only reported on Linux
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    (0, 0)
+  }
+}
+
+/// A size in bytes, displayed using binary units (e.g. `1.5 MiB`).
+struct ByteSize(usize);
+
+impl std::fmt::Display for ByteSize {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+    let (unit, scale) = UNITS
+      .iter()
+      .enumerate()
+      .map(|(index, unit)| (unit, 1usize << (index * 10)))
+      .take_while(|&(_, scale)| scale <= self.0.max(1))
+      .last()
+      .expect("bytes are always a unit");
+
+    if self.0 & (scale - 1) == 0 {
+      write!(f, "{} {}", self.0 / scale, unit)
+    } else {
+      write!(f, "{:.1} {}", self.0 as f64 / scale as f64, unit)
+    }
+  }
+}
+
+/// A descriptor for the physical memory usage of one or more regions.
+///
+/// All values are in bytes. This `struct` is obtained using [`Region::usage`]
+/// or [`query_usage`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+  rss: usize,
+  pss: usize,
+  shared_clean: usize,
+  shared_dirty: usize,
+  private_clean: usize,
+  private_dirty: usize,
+  swap: usize,
+  anonymous: usize,
+  locked: usize,
+}
+
+impl MemoryUsage {
+  /// Returns the amount of memory currently resident in RAM.
+  #[inline(always)]
+  pub fn rss(&self) -> usize {
+    self.rss
+  }
+
+  /// Returns the proportional set size.
+  ///
+  /// This is the resident memory, with each shared page divided by the number
+  /// of processes sharing it.
+  #[inline(always)]
+  pub fn pss(&self) -> usize {
+    self.pss
+  }
+
+  /// Returns the amount of unmodified resident memory shared with other
+  /// processes.
+  #[inline(always)]
+  pub fn shared_clean(&self) -> usize {
+    self.shared_clean
+  }
+
+  /// Returns the amount of modified resident memory shared with other
+  /// processes.
+  #[inline(always)]
+  pub fn shared_dirty(&self) -> usize {
+    self.shared_dirty
+  }
+
+  /// Returns the amount of unmodified resident memory private to the process.
+  #[inline(always)]
+  pub fn private_clean(&self) -> usize {
+    self.private_clean
+  }
+
+  /// Returns the amount of modified resident memory private to the process.
+  #[inline(always)]
+  pub fn private_dirty(&self) -> usize {
+    self.private_dirty
+  }
+
+  /// Returns the amount of memory that has been swapped out.
+  #[inline(always)]
+  pub fn swap(&self) -> usize {
+    self.swap
+  }
+
+  /// Returns the amount of resident memory that is not backed by a file.
+  #[inline(always)]
+  pub fn anonymous(&self) -> usize {
+    self.anonymous
+  }
+
+  /// Returns the amount of memory that is locked to RAM.
+  #[inline(always)]
+  pub fn locked(&self) -> usize {
+    self.locked
+  }
+}
+
 bitflags! {
   /// A bitflag of zero or more protection attributes.
   ///
@@ -245,10 +556,15 @@ bitflags! {
   /// combination of flags may not be applicable, depending on the OS (e.g macOS
   /// enforces executable pages to be readable, OpenBSD requires W^X).
   ///
+  /// A protection is displayed as `rwx` (with `-` in place of absent flags),
+  /// followed by the names of any OS-specific flags (e.g. `r-x+bti`).
+  ///
   /// # OS-Specific Behavior
   ///
-  /// On Unix `Protection::from_bits_unchecked` can be used to apply
-  /// non-standard flags (e.g. `PROT_BTI`).
+  /// On Linux, additional flags are available (e.g. `Protection::BTI`),
+  /// which are passed as is to `mmap` and `mprotect`. On other Unix systems,
+  /// `Protection::from_bits_unchecked` can be used to apply non-standard
+  /// flags.
   ///
   /// # Examples
   ///
@@ -276,19 +592,140 @@ bitflags! {
     const READ_WRITE_EXECUTE = (Self::READ.bits | Self::WRITE.bits | Self::EXECUTE.bits);
     /// Write and execute shorthand.
     const WRITE_EXECUTE = (Self::WRITE.bits | Self::EXECUTE.bits);
+    /// Atomic operations may be used on the pages, `PROT_SEM` (Linux).
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const SEM = if cfg!(any(target_arch = "mips", target_arch = "mips64")) {
+      1 << 4
+    } else {
+      1 << 3
+    };
+    /// Branch target identification is enforced, `PROT_BTI` (Linux, aarch64).
+    #[cfg(all(any(target_os = "linux", target_os = "android"), target_arch = "aarch64"))]
+    const BTI = (1 << 4);
+    /// Memory tagging is allowed, `PROT_MTE` (Linux, aarch64).
+    #[cfg(all(any(target_os = "linux", target_os = "android"), target_arch = "aarch64"))]
+    const MTE = (1 << 5);
+    /// The change extends to the start of a region that grows downwards,
+    /// `PROT_GROWSDOWN` (Linux). This only applies to `mprotect`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const GROWSDOWN = (1 << 24);
+    /// The change extends to the end of a region that grows upwards,
+    /// `PROT_GROWSUP` (Linux). This only applies to `mprotect`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const GROWSUP = (1 << 25);
   }
 }
 
+bitflags! {
+  /// A bitflag of zero or more kernel flags of a region.
+  ///
+  /// These describe properties of a region that are not reflected by its
+  /// protection, as reported in the `VmFlags` field of `/proc/[pid]/smaps`.
+  /// Flags that are unknown to this crate are ignored.
+  ///
+  /// This is obtained using [`Region::vm_flags`].
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// # fn main() -> region::Result<()> {
+  /// # #[cfg(target_os = "linux")] {
+  /// use region::VmFlags;
+  ///
+  /// let data = [0; 100];
+  /// let _guard = region::lock(data.as_ptr(), data.len())?;
+  /// let region = region::query_detailed(data.as_ptr())?;
+  ///
+  /// assert!(region.vm_flags().unwrap().contains(VmFlags::LOCKED));
+  /// # }
+  /// # Ok(())
+  /// # }
+  /// ```
+  #[derive(Default)]
+  pub struct VmFlags: u32 {
+    /// The region grows downwards (e.g. a stack), `gd`.
+    const GROWSDOWN = (1 << 0);
+    /// The region grows upwards, `gu`.
+    const GROWSUP = (1 << 1);
+    /// The pages of the region are locked to RAM, `lo`.
+    const LOCKED = (1 << 2);
+    /// The pages of the region are locked to RAM once faulted in, `lf`.
+    const LOCKONFAULT = (1 << 3);
+    /// The region is a memory mapped I/O area, `io`.
+    const IO = (1 << 4);
+    /// The region consists of raw page frames, `pf`.
+    const PFNMAP = (1 << 5);
+    /// The region is expected to be read sequentially, `sr`.
+    const SEQUENTIAL_READ = (1 << 6);
+    /// The region is expected to be read randomly, `rr`.
+    const RANDOM_READ = (1 << 7);
+    /// The region is not copied on fork (`MADV_DONTFORK`), `dc`.
+    const DONTFORK = (1 << 8);
+    /// The region cannot be expanded using `mremap`, `de`.
+    const DONTEXPAND = (1 << 9);
+    /// The region is accounted for (i.e. it is committed), `ac`.
+    const ACCOUNT = (1 << 10);
+    /// The region has no swap space reserved, `nr`.
+    const NORESERVE = (1 << 11);
+    /// The region is backed by huge TLB pages, `ht`.
+    const HUGETLB = (1 << 12);
+    /// The region is zeroed in the child on fork (`MADV_WIPEONFORK`), `wf`.
+    const WIPEONFORK = (1 << 13);
+    /// The region is excluded from core dumps (`MADV_DONTDUMP`), `dd`.
+    const DONTDUMP = (1 << 14);
+    /// The region is soft-dirty, `sd`.
+    const SOFT_DIRTY = (1 << 15);
+    /// The region consists of mixed page frames and pages, `mm`.
+    const MIXEDMAP = (1 << 16);
+    /// The region is advised to use transparent huge pages, `hg`.
+    const HUGEPAGE = (1 << 17);
+    /// The region is advised to not use transparent huge pages, `nh`.
+    const NOHUGEPAGE = (1 << 18);
+    /// The region is mergeable by KSM (`MADV_MERGEABLE`), `mg`.
+    const MERGEABLE = (1 << 19);
+    /// The region is a shadow stack, `ss`.
+    const SHADOW_STACK = (1 << 20);
+    /// The region is sealed (see `mseal(2)`), `sl`.
+    const SEALED = (1 << 21);
+    /// The region is protected by branch target identification (aarch64), `bt`.
+    const BTI = (1 << 22);
+    /// The region allows memory tagging (aarch64), `mt`.
+    const MTE = (1 << 23);
+  }
+}
+
+/// The symbols used for the textual representation of a protection.
+const PROTECTION_SYMBOLS: &[(Protection, char)] = &[
+  (Protection::READ, 'r'),
+  (Protection::WRITE, 'w'),
+  (Protection::EXECUTE, 'x'),
+];
+
+/// The names used for the textual representation of OS-specific protection
+/// flags.
+const PROTECTION_EXTENSIONS: &[(Protection, &str)] = &[
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  (Protection::SEM, "sem"),
+  #[cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    target_arch = "aarch64"
+  ))]
+  (Protection::BTI, "bti"),
+  #[cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    target_arch = "aarch64"
+  ))]
+  (Protection::MTE, "mte"),
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  (Protection::GROWSDOWN, "growsdown"),
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  (Protection::GROWSUP, "growsup"),
+];
+
 impl std::fmt::Display for Protection {
   #[inline]
   fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-    const MAPPINGS: &[(Protection, char)] = &[
-      (Protection::READ, 'r'),
-      (Protection::WRITE, 'w'),
-      (Protection::EXECUTE, 'x'),
-    ];
-
-    for (flag, symbol) in MAPPINGS {
+    for (flag, symbol) in PROTECTION_SYMBOLS {
       if self.contains(*flag) {
         write!(f, "{}", symbol)?;
       } else {
@@ -296,10 +733,69 @@ impl std::fmt::Display for Protection {
       }
     }
 
+    for (flag, name) in PROTECTION_EXTENSIONS {
+      if self.contains(*flag) {
+        write!(f, "+{}", name)?;
+      }
+    }
+
     Ok(())
   }
 }
 
+impl std::str::FromStr for Protection {
+  type Err = Error;
+
+  /// Parses a protection from its textual representation.
+  ///
+  /// The input consists of the symbols `r`, `w` and `x` (or `-` in their
+  /// absence), in that order (e.g. `r-x`). A trailing sharing symbol, as used
+  /// by `/proc/[pid]/maps` (i.e. `p` or `s`), is accepted and ignored. Any
+  /// OS-specific flags follow, prefixed by `+` (e.g. `r-x+bti`).
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// # fn main() -> region::Result<()> {
+  /// use region::Protection;
+  ///
+  /// assert_eq!("rw-".parse::<Protection>()?, Protection::READ_WRITE);
+  /// assert_eq!("r-xp".parse::<Protection>()?, Protection::READ_EXECUTE);
+  /// # Ok(())
+  /// # }
+  /// ```
+  #[inline]
+  fn from_str(input: &str) -> Result<Self> {
+    let mut parts = input.split('+');
+    let symbols = parts.next().unwrap_or_default();
+    let symbols = match symbols.len() {
+      4 if symbols.ends_with(&['p', 's'][..]) => &symbols[..3],
+      _ => symbols,
+    };
+
+    if symbols.len() != PROTECTION_SYMBOLS.len() {
+      return Err(Error::InvalidParameter("protection"));
+    }
+
+    let protection = PROTECTION_SYMBOLS.iter().zip(symbols.chars()).try_fold(
+      Protection::NONE,
+      |acc, (&(flag, symbol), input)| match input {
+        '-' => Ok(acc),
+        _ if input == symbol => Ok(acc | flag),
+        _ => Err(Error::InvalidParameter("protection")),
+      },
+    )?;
+
+    parts.try_fold(protection, |acc, input| {
+      PROTECTION_EXTENSIONS
+        .iter()
+        .find(|(_, name)| *name == input)
+        .map(|(flag, _)| acc | *flag)
+        .ok_or(Error::InvalidParameter("protection"))
+    })
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -312,6 +808,111 @@ mod tests {
     assert_eq!(Protection::WRITE.to_string(), "-w-");
   }
 
+  #[test]
+  fn region_implements_display() {
+    let mut region = Region {
+      base: 0x400000 as *const (),
+      size: 0x52000,
+      protection: Protection::READ_EXECUTE,
+      ..Default::default()
+    };
+
+    assert_eq!(
+      region.to_string(),
+      "00400000-00452000 r-xp 00000000 00:00 0"
+    );
+    assert_eq!(format!("{:#}", region), "0x400000-0x452000 r-xp 328 KiB");
+
+    region.shared = true;
+    region.name = Some("[anon:region]".to_string());
+    assert_eq!(
+      region.to_string(),
+      "00400000-00452000 r-xs 00000000 00:00 0 [anon:region]"
+    );
+  }
+
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn region_with_file_implements_display() {
+    let region = Region {
+      base: 0x400000 as *const (),
+      size: 0x180000,
+      protection: Protection::READ_WRITE,
+      file: Some(MappedFile {
+        path: "/usr/bin/head".into(),
+        offset: 0x1000,
+        device: libc::makedev(8, 2) as u64,
+        inode: 16088,
+        deleted: true,
+      }),
+      ..Default::default()
+    };
+
+    assert_eq!(
+      region.to_string(),
+      "00400000-00580000 rw-p 00001000 08:02 16088 /usr/bin/head (deleted)"
+    );
+    assert_eq!(
+      format!("{:#}", region),
+      "0x400000-0x580000 rw-p 1.5 MiB /usr/bin/head (deleted)"
+    );
+  }
+
+  #[test]
+  fn byte_size_implements_display() {
+    assert_eq!(ByteSize(0).to_string(), "0 B");
+    assert_eq!(ByteSize(1023).to_string(), "1023 B");
+    assert_eq!(ByteSize(4096).to_string(), "4 KiB");
+    assert_eq!(ByteSize(0x180000).to_string(), "1.5 MiB");
+    assert_eq!(ByteSize(1 << 30).to_string(), "1 GiB");
+  }
+
+  #[test]
+  fn protection_implements_from_str() -> Result<()> {
+    assert_eq!("---".parse::<Protection>()?, Protection::NONE);
+    assert_eq!("rwx".parse::<Protection>()?, Protection::READ_WRITE_EXECUTE);
+    assert_eq!("r-xp".parse::<Protection>()?, Protection::READ_EXECUTE);
+    assert_eq!("rw-s".parse::<Protection>()?, Protection::READ_WRITE);
+
+    for input in ["", "rw", "wrx", "rwxx", "r-x-", "rwxps", "R--"].iter() {
+      assert!(matches!(
+        input.parse::<Protection>(),
+        Err(Error::InvalidParameter(_))
+      ));
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn protection_display_round_trips() -> Result<()> {
+    let extensions = PROTECTION_EXTENSIONS.len();
+
+    for mask in 0..(1 << (extensions + PROTECTION_SYMBOLS.len())) {
+      let protection = PROTECTION_SYMBOLS
+        .iter()
+        .map(|(flag, _)| flag)
+        .chain(PROTECTION_EXTENSIONS.iter().map(|(flag, _)| flag))
+        .enumerate()
+        .filter(|(index, _)| mask & (1 << index) != 0)
+        .fold(Protection::NONE, |acc, (_, flag)| acc | *flag);
+      assert_eq!(protection.to_string().parse::<Protection>()?, protection);
+    }
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn protection_displays_extensions() -> Result<()> {
+    let protection = Protection::READ_WRITE | Protection::SEM | Protection::GROWSDOWN;
+    assert_eq!(protection.to_string(), "rw-+sem+growsdown");
+    assert_eq!("rw-+growsdown+sem".parse::<Protection>()?, protection);
+    assert!(matches!(
+      "rw-+bogus".parse::<Protection>(),
+      Err(Error::InvalidParameter(_))
+    ));
+    Ok(())
+  }
+
   #[cfg(unix)]
   pub mod util {
     use crate::{page, Protection};
@@ -330,6 +931,7 @@ mod tests {
 
     #[allow(clippy::fallible_impl_from)]
     impl From<Protection> for &'static [MapOption] {
+      #[inline]
       fn from(protection: Protection) -> Self {
         match protection {
           Protection::NONE => &[],
@@ -18,10 +18,10 @@ use crate::{os, util, Result};
 /// # Errors
 ///
 /// - If an interaction with the underlying operating system fails, an error
-/// will be returned.
+///   will be returned.
 /// - If size is zero,
-/// [`Error::InvalidParameter`](crate::Error::InvalidParameter) will be
-/// returned.
+///   [`Error::InvalidParameter`](crate::Error::InvalidParameter) will be
+///   returned.
 ///
 /// # Examples
 ///
@@ -53,10 +53,10 @@ pub fn lock<T>(address: *const T, size: usize) -> Result<LockGuard> {
 /// # Errors
 ///
 /// - If an interaction with the underlying operating system fails, an error
-/// will be returned.
+///   will be returned.
 /// - If size is zero,
-/// [`Error::InvalidParameter`](crate::Error::InvalidParameter) will be
-/// returned.
+///   [`Error::InvalidParameter`](crate::Error::InvalidParameter) will be
+///   returned.
 #[inline]
 pub fn unlock<T>(address: *const T, size: usize) -> Result<()> {
   let (address, size) = util::round_to_page_boundaries(address, size)?;
@@ -113,4 +113,20 @@ mod tests {
     std::mem::forget(lock(map.as_ptr(), page::size())?);
     unlock(map.as_ptr(), page::size())
   }
+
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn lock_is_reported_by_vm_flags() -> Result<()> {
+    use crate::{query_detailed, VmFlags};
+
+    let map = alloc_pages(&[Protection::READ_WRITE]);
+    let guard = lock(map.as_ptr(), page::size())?;
+    let flags = query_detailed(map.as_ptr())?.vm_flags().unwrap();
+    assert!(flags.contains(VmFlags::LOCKED));
+
+    std::mem::drop(guard);
+    let flags = query_detailed(map.as_ptr())?.vm_flags().unwrap();
+    assert!(!flags.contains(VmFlags::LOCKED));
+    Ok(())
+  }
 }
@@ -0,0 +1,181 @@
+use crate::{os, page, Allocation, Error, Protection, Result};
+use std::fs::File;
+
+/// Maps a file into memory, with a defined protection.
+///
+/// The mapping is owned by the returned [`Allocation`], and unmapped once it's
+/// dropped. The file itself may be closed whilst the mapping remains in use.
+///
+/// Shared mappings propagate writes to the file (and other mappings of it),
+/// whilst private mappings are copy-on-write, and their modifications are
+/// never written back to the file. To synchronize a shared mapping with the
+/// file, use [`Allocation::flush`] or [`Allocation::flush_async`].
+///
+/// # Parameters
+///
+/// - The offset must be aligned to a page boundary.
+/// - The size may not be zero.
+/// - The size is rounded up to the closest page boundary.
+///
+/// The file must be opened with access compatible with the protection (e.g.
+/// write access for a shared writable mapping). Accessing pages of the mapping
+/// beyond the end of the file results in a fault (e.g. `SIGBUS`).
+///
+/// # Errors
+///
+/// - If an interaction with the underlying operating system fails, an error
+///   will be returned.
+/// - If size is zero, or the offset is not aligned to a page boundary,
+///   [`Error::InvalidParameter`] will be returned.
+///
+/// # Safety
+///
+/// The file's contents may be modified (or truncated) by other mappings, or
+/// processes, whilst it's mapped. Any such modification is undefined behavior
+/// when the mapping is accessed through a reference (e.g. using
+/// [`Allocation::as_slice`]), and accessing pages beyond the end of a
+/// truncated file results in a fault. The caller must ensure that the file is
+/// not modified whilst the mapping is accessed this way.
+///
+/// # OS-Specific Behavior
+///
+/// On Windows, the offset must be aligned to the allocation granularity (most
+/// commonly 64KB), rather than the page size.
+///
+/// # Implementation
+///
+/// This function is implemented using `CreateFileMappingW` and
+/// `MapViewOfFile` on Windows, and `mmap` on POSIX.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use region::Protection;
+///
+/// let path = std::env::temp_dir().join("region-map-file-example");
+/// std::fs::write(&path, b"hello")?;
+/// let file = std::fs::File::open(&path)?;
+///
+/// let memory = unsafe { region::map_file(&file, 0, 5, Protection::READ, false)? };
+/// let data = unsafe { std::slice::from_raw_parts(memory.as_ptr::<u8>(), 5) };
+/// assert_eq!(data, b"hello");
+/// # drop(memory);
+/// # std::fs::remove_file(path)?;
+/// # Ok(())
+/// # }
+/// ```
+#[inline]
+pub unsafe fn map_file(
+  file: &File,
+  offset: u64,
+  size: usize,
+  protection: Protection,
+  shared: bool,
+) -> Result<Allocation> {
+  if size == 0 {
+    return Err(Error::InvalidParameter("size"));
+  }
+
+  if offset & (page::size() as u64 - 1) != 0 {
+    return Err(Error::InvalidParameter("offset"));
+  }
+
+  let size = page::ceil(size as *const ()) as usize;
+
+  let base = os::map_file(file, offset, size, protection, shared)?;
+  Ok(Allocation::from_raw_parts(base as *mut (), size))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs::OpenOptions;
+  use std::io::{Read, Seek, SeekFrom, Write};
+  use std::path::PathBuf;
+
+  /// Creates a temporary file, spanning two pages.
+  fn create_file(name: &str) -> Result<(File, PathBuf)> {
+    let path = std::env::temp_dir().join(format!("region-{}-{}", name, std::process::id()));
+    let mut file = OpenOptions::new()
+      .read(true)
+      .write(true)
+      .create(true)
+      .truncate(true)
+      .open(&path)
+      .map_err(Error::SystemCall)?;
+
+    let mut contents = vec![0xAA; page::size()];
+    contents.extend(vec![0xBB; page::size()]);
+    file.write_all(&contents).map_err(Error::SystemCall)?;
+    Ok((file, path))
+  }
+
+  #[test]
+  fn map_file_rejects_invalid_parameters() -> Result<()> {
+    let (file, path) = create_file("invalid")?;
+
+    assert!(matches!(
+      unsafe { map_file(&file, 0, 0, Protection::READ, false) },
+      Err(Error::InvalidParameter(_))
+    ));
+    assert!(matches!(
+      unsafe { map_file(&file, 1, 1, Protection::READ, false) },
+      Err(Error::InvalidParameter(_))
+    ));
+
+    std::mem::drop(file);
+    std::fs::remove_file(path).map_err(Error::SystemCall)
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn map_file_maps_contents_at_offset() -> Result<()> {
+    let (file, path) = create_file("offset")?;
+    let memory = unsafe { map_file(&file, page::size() as u64, 1, Protection::READ, false)? };
+
+    assert_eq!(memory.len(), page::size());
+    assert_eq!(unsafe { *memory.as_ptr::<u8>() }, 0xBB);
+
+    let region = crate::query(memory.as_ptr::<()>())?;
+    assert_eq!(region.protection(), Protection::READ);
+    assert!(!region.is_shared());
+
+    std::mem::drop((memory, file));
+    std::fs::remove_file(path).map_err(Error::SystemCall)
+  }
+
+  #[test]
+  fn map_file_shared_writes_are_flushed() -> Result<()> {
+    let (mut file, path) = create_file("shared")?;
+    let memory = unsafe { map_file(&file, 0, page::size(), Protection::READ_WRITE, true)? };
+
+    unsafe { *(memory.as_ptr::<u8>() as *mut u8) = 0xCC };
+    memory.flush()?;
+
+    let mut byte = [0];
+    file.seek(SeekFrom::Start(0)).map_err(Error::SystemCall)?;
+    file.read_exact(&mut byte).map_err(Error::SystemCall)?;
+    assert_eq!(byte[0], 0xCC);
+
+    std::mem::drop((memory, file));
+    std::fs::remove_file(path).map_err(Error::SystemCall)
+  }
+
+  #[test]
+  fn map_file_private_writes_are_not_propagated() -> Result<()> {
+    let (mut file, path) = create_file("private")?;
+    let memory = unsafe { map_file(&file, 0, page::size(), Protection::READ_WRITE, false)? };
+
+    unsafe { *(memory.as_ptr::<u8>() as *mut u8) = 0xCC };
+    memory.flush_async()?;
+
+    let mut byte = [0];
+    file.seek(SeekFrom::Start(0)).map_err(Error::SystemCall)?;
+    file.read_exact(&mut byte).map_err(Error::SystemCall)?;
+    assert_eq!(byte[0], 0xAA);
+
+    std::mem::drop((memory, file));
+    std::fs::remove_file(path).map_err(Error::SystemCall)
+  }
+}
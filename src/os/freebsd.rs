@@ -50,7 +50,7 @@ impl Iterator for QueryIter {
     self.vmmap_index += 1;
     Some(Ok(Region {
       base: entry.kve_start as *const _,
-      protection: Protection::from_native(entry.kve_protection),
+      protection: Protection::from_kve_protection(entry.kve_protection),
       shared: entry.kve_type == KVME_TYPE_DEFAULT,
       size: (entry.kve_end - entry.kve_start) as _,
       ..Default::default()
@@ -65,7 +65,7 @@ impl Drop for QueryIter {
 }
 
 impl Protection {
-  fn from_native(protection: c_int) -> Self {
+  fn from_kve_protection(protection: c_int) -> Self {
     const MAPPINGS: &[(c_int, Protection)] = &[
       (KVME_PROT_READ, Protection::READ),
       (KVME_PROT_WRITE, Protection::WRITE),
@@ -88,9 +88,15 @@ mod tests {
     let rw = KVME_PROT_READ | KVME_PROT_WRITE;
     let rwx = rw | KVME_PROT_EXEC;
 
-    assert_eq!(Protection::from_native(0), Protection::NONE);
-    assert_eq!(Protection::from_native(KVME_PROT_READ), Protection::READ);
-    assert_eq!(Protection::from_native(rw), Protection::READ_WRITE);
-    assert_eq!(Protection::from_native(rwx), Protection::READ_WRITE_EXECUTE);
+    assert_eq!(Protection::from_kve_protection(0), Protection::NONE);
+    assert_eq!(
+      Protection::from_kve_protection(KVME_PROT_READ),
+      Protection::READ
+    );
+    assert_eq!(Protection::from_kve_protection(rw), Protection::READ_WRITE);
+    assert_eq!(
+      Protection::from_kve_protection(rwx),
+      Protection::READ_WRITE_EXECUTE
+    );
   }
 }
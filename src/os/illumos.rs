@@ -57,7 +57,7 @@ impl Iterator for QueryIter {
     self.vmmap_index += 1;
     Some(Ok(Region {
       base: map.pr_vaddr,
-      protection: Protection::from_native(map.pr_mflags),
+      protection: Protection::from_ma_flags(map.pr_mflags),
       shared: map.pr_mflags & MA_SHARED != 0,
       size: map.pr_size,
       ..Default::default()
@@ -66,7 +66,7 @@ impl Iterator for QueryIter {
 }
 
 impl Protection {
-  fn from_native(protection: i32) -> Self {
+  fn from_ma_flags(protection: i32) -> Self {
     const MAPPINGS: &[(i32, Protection)] = &[
       (MA_READ, Protection::READ),
       (MA_WRITE, Protection::WRITE),
@@ -112,9 +112,12 @@ mod tests {
     let rw = MA_READ | MA_WRITE;
     let rwx = rw | MA_EXEC;
 
-    assert_eq!(Protection::from_native(0), Protection::NONE);
-    assert_eq!(Protection::from_native(MA_READ), Protection::READ);
-    assert_eq!(Protection::from_native(rw), Protection::READ_WRITE);
-    assert_eq!(Protection::from_native(rwx), Protection::READ_WRITE_EXECUTE);
+    assert_eq!(Protection::from_ma_flags(0), Protection::NONE);
+    assert_eq!(Protection::from_ma_flags(MA_READ), Protection::READ);
+    assert_eq!(Protection::from_ma_flags(rw), Protection::READ_WRITE);
+    assert_eq!(
+      Protection::from_ma_flags(rwx),
+      Protection::READ_WRITE_EXECUTE
+    );
   }
 }
@@ -1,22 +1,63 @@
-use crate::{Error, Protection, Region, Result};
-use std::fs;
+use crate::{Advice, Error, MappedFile, MemoryUsage, Process, Protection, Region, Result, VmFlags};
+use std::ffi::CStr;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::mem::size_of;
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU8, Ordering};
 
 pub struct QueryIter {
-  proc_maps: String,
+  source: Source,
+  lower_bound: usize,
   upper_bound: usize,
-  offset: usize,
+}
+
+enum Source {
+  /// Regions are queried one at a time using the `PROCMAP_QUERY` ioctl, with
+  /// a buffer (reused across queries) for their names.
+  Ioctl { file: File, name: Vec<u8> },
+  /// Regions are parsed from a snapshot of the procfs file.
+  Text { proc_maps: String, offset: usize },
 }
 
 impl QueryIter {
   pub fn new(origin: *const (), size: usize) -> Result<Self> {
-    // Do not use a buffered reader here to avoid multiple read(2) calls to the
-    // proc file, ensuring a consistent snapshot of the virtual memory.
-    let proc_maps = fs::read_to_string("/proc/self/maps").map_err(Error::SystemCall)?;
+    Self::with_options(None, false, origin, size)
+  }
+
+  pub fn with_options(
+    process: Option<&Process>,
+    detailed: bool,
+    origin: *const (),
+    size: usize,
+  ) -> Result<Self> {
+    // The 'smaps' file contains the same entries as 'maps', each followed by
+    // lines of additional attributes (at the cost of a slower read).
+    let path = procfs_path(process, if detailed { "smaps" } else { "maps" });
+    let file = File::open(path).map_err(Error::SystemCall)?;
+
+    // Prefer querying the regions of interest directly (Linux 6.11+), instead
+    // of parsing every region of the process.
+    let source = if !detailed && procmap_query_is_supported(&file) {
+      Source::Ioctl {
+        file,
+        name: vec![0u8; libc::PATH_MAX as usize],
+      }
+    } else {
+      Source::read(file)?
+    };
+
+    // The PID may have been recycled whilst the maps were opened, so verify
+    // that the process still exists afterwards (only possible with a pidfd).
+    if let Some(process) = process {
+      process.ensure_alive()?;
+    }
 
     Ok(Self {
-      proc_maps,
+      source,
+      lower_bound: origin as usize,
       upper_bound: (origin as usize).saturating_add(size),
-      offset: 0,
     })
   }
 
@@ -29,13 +70,331 @@ impl Iterator for QueryIter {
   type Item = Result<Region>;
 
   fn next(&mut self) -> Option<Self::Item> {
-    let (line, _) = self.proc_maps.get(self.offset..)?.split_once('\n')?;
-    self.offset += line.len() + 1;
+    let (file, name) = match self.source {
+      Source::Ioctl {
+        ref file,
+        ref mut name,
+      } => (file, name),
+      Source::Text { .. } => loop {
+        match self.source.next_text()? {
+          Ok(region) if region.as_range().end <= self.lower_bound => continue,
+          result => return Some(result),
+        }
+      },
+    };
+
+    if self.lower_bound >= self.upper_bound {
+      return None;
+    }
+
+    match procmap_query(file, name, self.lower_bound) {
+      Ok(Some(region)) => {
+        self.lower_bound = region.as_range().end;
+        Some(Ok(region))
+      }
+      Ok(None) => {
+        // Special mappings that do not belong to the process (i.e. the gate
+        // area, such as '[vsyscall]') are not found by the ioctl, therefore the
+        // remainder of the regions are parsed from the procfs file.
+        let file = match std::mem::replace(&mut self.source, Source::empty()) {
+          Source::Ioctl { file, .. } => file,
+          Source::Text { .. } => unreachable!(),
+        };
+
+        match Source::read(file) {
+          Ok(source) => self.source = source,
+          Err(error) => return Some(Err(error)),
+        }
+        self.next()
+      }
+      Err(error) => Some(Err(error)),
+    }
+  }
+}
+
+impl Source {
+  fn empty() -> Self {
+    Source::Text {
+      proc_maps: String::new(),
+      offset: 0,
+    }
+  }
+
+  fn read(mut file: File) -> Result<Self> {
+    // Do not use a buffered reader here to avoid multiple read(2) calls to the
+    // proc file, ensuring a consistent snapshot of the virtual memory.
+    let mut proc_maps = String::new();
+    file
+      .read_to_string(&mut proc_maps)
+      .map_err(Error::SystemCall)?;
+
+    Ok(Source::Text {
+      proc_maps,
+      offset: 0,
+    })
+  }
+
+  fn next_text(&mut self) -> Option<Result<Region>> {
+    let line = self.next_line()?;
+    let mut region = match parse_procfs_line(line) {
+      Some(region) => region,
+      None => return Some(Err(Error::ProcfsInput(line.to_string()))),
+    };
+
+    let mut usage = None;
+    while let Some((key, value)) = self.next_attribute() {
+      if key == "VmFlags" {
+        region.vm_flags = Some(parse_smaps_vm_flags(value));
+        region.max_protection = Some(parse_smaps_max_protection(value));
+        continue;
+      }
+
+      let usage = usage.get_or_insert_with(MemoryUsage::default);
+      if !parse_smaps_usage(usage, key, value) {
+        return Some(Err(Error::ProcfsInput(format!("{}: {}", key, value))));
+      }
+    }
+
+    region.usage = usage;
+    Some(Ok(region))
+  }
+
+  fn next_line(&mut self) -> Option<&str> {
+    match self {
+      Source::Text { proc_maps, offset } => {
+        let (line, _) = proc_maps.get(*offset..)?.split_once('\n')?;
+        *offset += line.len() + 1;
+        Some(line)
+      }
+      Source::Ioctl { .. } => None,
+    }
+  }
+
+  fn next_attribute(&mut self) -> Option<(&str, &str)> {
+    match self {
+      Source::Text { proc_maps, offset } => {
+        let (line, _) = proc_maps.get(*offset..)?.split_once('\n')?;
+        let attribute = parse_smaps_attribute(line)?;
+        *offset += line.len() + 1;
+        Some(attribute)
+      }
+      Source::Ioctl { .. } => None,
+    }
+  }
+}
 
-    Some(parse_procfs_line(line).ok_or_else(|| Error::ProcfsInput(line.to_string())))
+/// Returns the memory usage totals of a process.
+pub fn query_usage(process: Option<&Process>) -> Result<MemoryUsage> {
+  let input = match fs::read_to_string(procfs_path(process, "smaps_rollup")) {
+    Ok(input) => input,
+    // The rollup file is only available on Linux 4.14+
+    Err(ref error) if error.kind() == io::ErrorKind::NotFound => {
+      return QueryIter::with_options(process, true, std::ptr::null(), usize::MAX)?.try_fold(
+        MemoryUsage::default(),
+        |total, region| {
+          let usage = region?.usage.unwrap_or_default();
+          Ok(MemoryUsage {
+            rss: total.rss + usage.rss,
+            pss: total.pss + usage.pss,
+            shared_clean: total.shared_clean + usage.shared_clean,
+            shared_dirty: total.shared_dirty + usage.shared_dirty,
+            private_clean: total.private_clean + usage.private_clean,
+            private_dirty: total.private_dirty + usage.private_dirty,
+            swap: total.swap + usage.swap,
+            anonymous: total.anonymous + usage.anonymous,
+            locked: total.locked + usage.locked,
+          })
+        },
+      );
+    }
+    Err(error) => return Err(Error::SystemCall(error)),
+  };
+
+  if let Some(process) = process {
+    process.ensure_alive()?;
+  }
+
+  // The first line is a header describing the entire address space
+  let mut usage = MemoryUsage::default();
+  for line in input.lines().skip(1) {
+    let (key, value) =
+      parse_smaps_attribute(line).ok_or_else(|| Error::ProcfsInput(line.to_string()))?;
+
+    if !parse_smaps_usage(&mut usage, key, value) {
+      return Err(Error::ProcfsInput(line.to_string()));
+    }
+  }
+
+  Ok(usage)
+}
+
+/// Returns the path of a file in a process' procfs directory.
+fn procfs_path(process: Option<&Process>, file: &str) -> PathBuf {
+  match process {
+    Some(process) => format!("/proc/{}/{}", process.pid(), file).into(),
+    None => Path::new("/proc/self").join(file),
   }
 }
 
+/// Opens a file descriptor referring to a process.
+///
+/// Returns `None` if the kernel does not support pidfds (i.e. pre Linux 5.3).
+pub fn pidfd_open(pid: libc::pid_t) -> Result<Option<File>> {
+  match unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) } {
+    -1 => match io::Error::last_os_error() {
+      error if error.raw_os_error() == Some(libc::ENOSYS) => Ok(None),
+      error => Err(Error::SystemCall(error)),
+    },
+    fd => Ok(Some(unsafe { File::from_raw_fd(fd as libc::c_int) })),
+  }
+}
+
+pub unsafe fn resize(
+  base: *const (),
+  size: usize,
+  new_size: usize,
+  may_move: bool,
+) -> Result<*const ()> {
+  let flags = if may_move { MREMAP_MAYMOVE } else { 0 };
+  remap(libc::syscall(libc::SYS_mremap, base, size, new_size, flags))
+}
+
+pub unsafe fn move_to(base: *const (), size: usize, address: *const ()) -> Result<()> {
+  let flags = MREMAP_MAYMOVE | MREMAP_FIXED;
+  remap(libc::syscall(
+    libc::SYS_mremap,
+    base,
+    size,
+    size,
+    flags,
+    address,
+  ))
+  .map(|_| ())
+}
+
+/// Translates the result of a `mremap` system call.
+fn remap(result: libc::c_long) -> Result<*const ()> {
+  match result as *mut libc::c_void {
+    libc::MAP_FAILED => Err(Error::SystemCall(io::Error::last_os_error())),
+    address => Ok(address as *const ()),
+  }
+}
+
+// These definitions come from <linux/mman.h>, since they're not exposed by
+// `libc` on all targets (e.g. Android).
+const MREMAP_MAYMOVE: libc::c_int = 1;
+const MREMAP_FIXED: libc::c_int = 2;
+
+// This definition comes from <linux/memfd.h>, since it's only exposed by recent
+// versions of `libc`.
+const MFD_EXEC: libc::c_uint = 0x10;
+
+/// Creates an anonymous, memory-backed, file, which may be mapped executable.
+pub fn memfd_create(name: &CStr) -> Result<File> {
+  let create = |flags: libc::c_uint| match unsafe {
+    libc::syscall(libc::SYS_memfd_create, name.as_ptr(), flags)
+  } {
+    -1 => Err(io::Error::last_os_error()),
+    fd => Ok(unsafe { File::from_raw_fd(fd as libc::c_int) }),
+  };
+
+  // Since Linux 6.3, files are non-executable by default if `vm.memfd_noexec`
+  // is set, whilst older kernels reject the flag (and are always executable).
+  create(libc::MFD_CLOEXEC | MFD_EXEC)
+    .or_else(|error| match error.raw_os_error() {
+      Some(libc::EINVAL) => create(libc::MFD_CLOEXEC),
+      _ => Err(error),
+    })
+    .map_err(Error::SystemCall)
+}
+
+/// Returns whether the process referred to by a pidfd is still alive.
+pub fn pidfd_is_alive(pidfd: &File) -> Result<bool> {
+  let result = unsafe {
+    libc::syscall(
+      libc::SYS_pidfd_send_signal,
+      pidfd.as_raw_fd(),
+      0,
+      std::ptr::null::<libc::siginfo_t>(),
+      0,
+    )
+  };
+
+  if result == 0 {
+    return Ok(true);
+  }
+
+  match io::Error::last_os_error() {
+    error if error.raw_os_error() == Some(libc::ESRCH) => Ok(false),
+    // The process exists, but the caller is not allowed to signal it
+    error if error.raw_os_error() == Some(libc::EPERM) => Ok(true),
+    error => Err(Error::SystemCall(error)),
+  }
+}
+
+/// Returns whether the kernel supports the `PROCMAP_QUERY` ioctl.
+fn procmap_query_is_supported(file: &File) -> bool {
+  const UNKNOWN: u8 = 0;
+  const SUPPORTED: u8 = 1;
+  const UNSUPPORTED: u8 = 2;
+  static STATE: AtomicU8 = AtomicU8::new(UNKNOWN);
+
+  match STATE.load(Ordering::Relaxed) {
+    SUPPORTED => return true,
+    UNSUPPORTED => return false,
+    _ => {}
+  }
+
+  // A query without a size is rejected with EINVAL if the ioctl is supported,
+  // whilst older kernels reject the unknown ioctl with ENOTTY.
+  let mut query = ProcmapQuery::default();
+  let result = unsafe { libc::ioctl(file.as_raw_fd(), PROCMAP_QUERY as _, &mut query) };
+  let supported = result == -1 && io::Error::last_os_error().raw_os_error() == Some(libc::EINVAL);
+
+  STATE.store(
+    if supported { SUPPORTED } else { UNSUPPORTED },
+    Ordering::Relaxed,
+  );
+  supported
+}
+
+/// Queries the region at, or the closest one after, an address.
+///
+/// Returns `None` if there are no regions at, or after, the address.
+fn procmap_query(file: &File, name: &mut [u8], address: usize) -> Result<Option<Region>> {
+  let mut query = ProcmapQuery {
+    size: size_of::<ProcmapQuery>() as u64,
+    query_flags: PROCMAP_QUERY_COVERING_OR_NEXT_VMA,
+    query_addr: address as u64,
+    vma_name_size: name.len() as u32,
+    vma_name_addr: name.as_mut_ptr() as u64,
+    ..Default::default()
+  };
+
+  if unsafe { libc::ioctl(file.as_raw_fd(), PROCMAP_QUERY as _, &mut query) } == -1 {
+    return match io::Error::last_os_error() {
+      error if error.raw_os_error() == Some(libc::ENOENT) => Ok(None),
+      error => Err(Error::SystemCall(error)),
+    };
+  }
+
+  // The name size includes the NUL terminator, if there is a name at all
+  let name = &name[..(query.vma_name_size as usize).saturating_sub(1)];
+  let path = String::from_utf8_lossy(name);
+
+  let mut region = Region {
+    base: query.vma_start as usize as *const _,
+    protection: Protection::from_procmap_flags(query.vma_flags),
+    shared: query.vma_flags & PROCMAP_QUERY_VMA_SHARED != 0,
+    size: (query.vma_end - query.vma_start) as usize,
+    ..Region::default()
+  };
+
+  let device = libc::makedev(query.dev_major, query.dev_minor) as u64;
+  set_procfs_path(&mut region, &path, query.vma_offset, device, query.inode);
+  Ok(Some(region))
+}
+
 /// Parses flags from /proc/[pid]/maps (e.g 'r--p').
 fn parse_procfs_flags(protection: &str) -> (Protection, bool) {
   const MAPPINGS: &[Protection] = &[Protection::READ, Protection::WRITE, Protection::EXECUTE];
@@ -51,29 +410,232 @@ fn parse_procfs_flags(protection: &str) -> (Protection, bool) {
 
 /// Parses a line from /proc/[pid]/maps.
 fn parse_procfs_line(input: &str) -> Option<Region> {
-  let mut parts = input.split_whitespace();
-  let mut memory = parts
-    .next()?
+  // The pathname is the only column that may contain whitespace, therefore the
+  // preceding columns are split off one at a time.
+  let (memory, input) = split_procfs_column(input);
+  let (flags, input) = split_procfs_column(input);
+  let (offset, input) = split_procfs_column(input);
+  let (device, input) = split_procfs_column(input);
+  let (inode, input) = split_procfs_column(input);
+  let path = input.trim_start_matches(' ');
+
+  let mut memory = memory
     .split('-')
     .filter_map(|value| usize::from_str_radix(value, 16).ok());
   let (lower, upper) = (memory.next()?, memory.next()?);
-
-  let flags = parts.next()?;
   let (protection, shared) = parse_procfs_flags(flags);
 
-  Some(Region {
+  let mut region = Region {
     base: lower as *const _,
     protection,
     shared,
     size: upper - lower,
     ..Region::default()
-  })
+  };
+
+  let (major, minor) = device.split_once(':')?;
+  let device = libc::makedev(
+    u32::from_str_radix(major, 16).ok()?,
+    u32::from_str_radix(minor, 16).ok()?,
+  ) as u64;
+
+  set_procfs_path(
+    &mut region,
+    path,
+    u64::from_str_radix(offset, 16).ok()?,
+    device,
+    inode.parse().ok()?,
+  );
+  Some(region)
+}
+
+/// Assigns the backing file, or pseudo path, described by procfs to a region.
+fn set_procfs_path(region: &mut Region, path: &str, offset: u64, device: u64, inode: u64) {
+  if path.starts_with('/') {
+    let (path, deleted) = match path.strip_suffix(" (deleted)") {
+      Some(path) => (path, true),
+      None => (path, false),
+    };
+
+    region.file = Some(MappedFile {
+      path: PathBuf::from(path),
+      offset,
+      device,
+      inode,
+      deleted,
+    });
+  } else if !path.is_empty() {
+    // Pseudo paths, e.g. '[heap]', '[vdso]' or '[anon:<name>]'
+    region.name = Some(path.to_string());
+  }
 }
 
+/// Parses an attribute line from /proc/[pid]/smaps (e.g 'Rss:    4 kB').
+fn parse_smaps_attribute(input: &str) -> Option<(&str, &str)> {
+  let (key, value) = split_procfs_column(input);
+  Some((key.strip_suffix(':')?, value.trim()))
+}
+
+/// Parses a memory usage attribute, returning false if the value is malformed.
+///
+/// Unknown attributes are ignored.
+fn parse_smaps_usage(usage: &mut MemoryUsage, key: &str, value: &str) -> bool {
+  let field = match key {
+    "Rss" => &mut usage.rss,
+    "Pss" => &mut usage.pss,
+    "Shared_Clean" => &mut usage.shared_clean,
+    "Shared_Dirty" => &mut usage.shared_dirty,
+    "Private_Clean" => &mut usage.private_clean,
+    "Private_Dirty" => &mut usage.private_dirty,
+    "Swap" => &mut usage.swap,
+    "Anonymous" => &mut usage.anonymous,
+    "Locked" => &mut usage.locked,
+    _ => return true,
+  };
+
+  match value.strip_suffix(" kB").map(str::parse::<usize>) {
+    Some(Ok(kilobytes)) => {
+      *field = kilobytes.saturating_mul(1024);
+      true
+    }
+    _ => false,
+  }
+}
+
+/// Parses the value of a 'VmFlags' attribute (e.g 'rd wr mr mw me ac').
+///
+/// Flags that are already described by other means (e.g. protection), or are
+/// unknown, are ignored.
+fn parse_smaps_vm_flags(input: &str) -> VmFlags {
+  const MAPPINGS: &[(&str, VmFlags)] = &[
+    ("gd", VmFlags::GROWSDOWN),
+    ("gu", VmFlags::GROWSUP),
+    ("lo", VmFlags::LOCKED),
+    ("lf", VmFlags::LOCKONFAULT),
+    ("io", VmFlags::IO),
+    ("pf", VmFlags::PFNMAP),
+    ("sr", VmFlags::SEQUENTIAL_READ),
+    ("rr", VmFlags::RANDOM_READ),
+    ("dc", VmFlags::DONTFORK),
+    ("de", VmFlags::DONTEXPAND),
+    ("ac", VmFlags::ACCOUNT),
+    ("nr", VmFlags::NORESERVE),
+    ("ht", VmFlags::HUGETLB),
+    ("wf", VmFlags::WIPEONFORK),
+    ("dd", VmFlags::DONTDUMP),
+    ("sd", VmFlags::SOFT_DIRTY),
+    ("mm", VmFlags::MIXEDMAP),
+    ("hg", VmFlags::HUGEPAGE),
+    ("nh", VmFlags::NOHUGEPAGE),
+    ("mg", VmFlags::MERGEABLE),
+    ("ss", VmFlags::SHADOW_STACK),
+    ("sl", VmFlags::SEALED),
+    ("bt", VmFlags::BTI),
+    ("mt", VmFlags::MTE),
+  ];
+
+  input
+    .split_whitespace()
+    .filter_map(|flag| MAPPINGS.iter().find(|(name, _)| *name == flag))
+    .fold(VmFlags::empty(), |acc, (_, flag)| acc | *flag)
+}
+
+/// Parses the maximum protection from the `may` flags of a smaps entry.
+fn parse_smaps_max_protection(input: &str) -> Protection {
+  const MAPPINGS: &[(&str, Protection)] = &[
+    ("mr", Protection::READ),
+    ("mw", Protection::WRITE),
+    ("me", Protection::EXECUTE),
+  ];
+
+  input
+    .split_whitespace()
+    .filter_map(|flag| MAPPINGS.iter().find(|(name, _)| *name == flag))
+    .fold(Protection::NONE, |acc, (_, flag)| acc | *flag)
+}
+
+/// Splits off the first whitespace separated column of a procfs line.
+fn split_procfs_column(input: &str) -> (&str, &str) {
+  let input = input.trim_start_matches(' ');
+  input.split_at(input.find(' ').unwrap_or(input.len()))
+}
+
+impl Advice {
+  /// Translates Linux-specific advice to its native `madvise` value.
+  pub(super) fn to_linux_native(self) -> Option<libc::c_int> {
+    match self {
+      Advice::Cold => Some(libc::MADV_COLD),
+      Advice::PageOut => Some(libc::MADV_PAGEOUT),
+      Advice::Mergeable => Some(libc::MADV_MERGEABLE),
+      Advice::Unmergeable => Some(libc::MADV_UNMERGEABLE),
+      Advice::HugePage => Some(libc::MADV_HUGEPAGE),
+      Advice::NoHugePage => Some(libc::MADV_NOHUGEPAGE),
+      Advice::PopulateRead => Some(libc::MADV_POPULATE_READ),
+      Advice::PopulateWrite => Some(libc::MADV_POPULATE_WRITE),
+      Advice::DontDump => Some(libc::MADV_DONTDUMP),
+      Advice::DoDump => Some(libc::MADV_DODUMP),
+      Advice::DontFork => Some(libc::MADV_DONTFORK),
+      Advice::DoFork => Some(libc::MADV_DOFORK),
+      Advice::WipeOnFork => Some(libc::MADV_WIPEONFORK),
+      Advice::KeepOnFork => Some(libc::MADV_KEEPONFORK),
+      _ => None,
+    }
+  }
+}
+
+impl Protection {
+  fn from_procmap_flags(flags: u64) -> Self {
+    const MAPPINGS: &[(u64, Protection)] = &[
+      (PROCMAP_QUERY_VMA_READABLE, Protection::READ),
+      (PROCMAP_QUERY_VMA_WRITABLE, Protection::WRITE),
+      (PROCMAP_QUERY_VMA_EXECUTABLE, Protection::EXECUTE),
+    ];
+
+    MAPPINGS
+      .iter()
+      .filter(|(flag, _)| flags & *flag == *flag)
+      .fold(Protection::NONE, |acc, (_, prot)| acc | *prot)
+  }
+}
+
+// These definitions come from <linux/fs.h>, describing the argument of the
+// `PROCMAP_QUERY` ioctl (available since Linux 6.11).
+#[repr(C)]
+#[derive(Default)]
+struct ProcmapQuery {
+  size: u64,
+  query_flags: u64,
+  query_addr: u64,
+  vma_start: u64,
+  vma_end: u64,
+  vma_flags: u64,
+  vma_page_size: u64,
+  vma_offset: u64,
+  inode: u64,
+  dev_major: u32,
+  dev_minor: u32,
+  vma_name_size: u32,
+  build_id_size: u32,
+  vma_name_addr: u64,
+  build_id_addr: u64,
+}
+
+// _IOWR('f', 17, struct procmap_query), which is encoded identically on all
+// architectures since the struct size fits within 13 bits.
+const PROCMAP_QUERY: libc::c_ulong = (3 << 30)
+  | ((size_of::<ProcmapQuery>() as libc::c_ulong) << 16)
+  | ((b'f' as libc::c_ulong) << 8)
+  | 17;
+
+const PROCMAP_QUERY_VMA_READABLE: u64 = 0x01;
+const PROCMAP_QUERY_VMA_WRITABLE: u64 = 0x02;
+const PROCMAP_QUERY_VMA_EXECUTABLE: u64 = 0x04;
+const PROCMAP_QUERY_VMA_SHARED: u64 = 0x08;
+const PROCMAP_QUERY_COVERING_OR_NEXT_VMA: u64 = 0x10;
+
 #[cfg(test)]
 mod tests {
-  use super::{parse_procfs_flags, parse_procfs_line};
-  use crate::Protection;
+  use super::*;
 
   #[test]
   fn procfs_flags_are_parsed() {
@@ -97,5 +659,153 @@ mod tests {
     assert_eq!(region.len(), 0x9000);
     assert!(!region.is_guarded());
     assert!(region.is_shared());
+
+    let file = region.file().unwrap();
+    assert_eq!(file.path(), Path::new("/usr/bin/head"));
+    assert_eq!(file.offset(), 0);
+    assert_eq!(file.device(), libc::makedev(8, 0) as u64);
+    assert_eq!(file.inode(), 16088);
+    assert!(!file.is_deleted());
+  }
+
+  #[test]
+  fn procfs_regions_with_special_paths_are_parsed() {
+    let line = "7f0000000000-7f0000001000 rw-s 00002000 00:01 1027   /memfd:jit code (deleted)";
+    let file = parse_procfs_line(line).unwrap().file().cloned().unwrap();
+    assert_eq!(file.path(), Path::new("/memfd:jit code"));
+    assert_eq!(file.offset(), 0x2000);
+    assert_eq!(file.inode(), 1027);
+    assert!(file.is_deleted());
+
+    let line = "55d4a1a4e000-55d4a1a6f000 rw-p 00000000 00:00 0                          [heap]";
+    let region = parse_procfs_line(line).unwrap();
+    assert_eq!(region.name(), Some("[heap]"));
+    assert!(region.file().is_none());
+
+    let line = "7ffd2b9f2000-7ffd2b9f4000 r-xp 00000000 00:00 0";
+    let region = parse_procfs_line(line).unwrap();
+    assert_eq!(region.name(), None);
+    assert!(region.file().is_none());
+  }
+
+  #[test]
+  fn procfs_regions_are_displayed_as_parsed() -> Result<()> {
+    let proc_maps = fs::read_to_string("/proc/self/maps").map_err(Error::SystemCall)?;
+
+    for line in proc_maps.lines() {
+      let region = parse_procfs_line(line).unwrap();
+      let expected = line.split_whitespace().collect::<Vec<_>>().join(" ");
+      assert_eq!(region.to_string(), expected);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn smaps_regions_are_parsed() {
+    let input = "\
+00400000-00401000 r-xp 00000000 08:00 16088 /usr/bin/head
+Size:                  4 kB
+Rss:                   4 kB
+Pss:                   2 kB
+Shared_Clean:          4 kB
+Private_Dirty:         0 kB
+VmFlags: rd ex mr mw me
+00401000-00403000 rw-p 00000000 00:00 0
+Rss:                   8 kB
+Anonymous:             8 kB
+Swap:                  0 kB
+";
+    let mut iter = QueryIter {
+      source: Source::Text {
+        proc_maps: input.to_string(),
+        offset: 0,
+      },
+      lower_bound: 0,
+      upper_bound: usize::MAX,
+    };
+
+    let region = iter.next().unwrap().unwrap();
+    assert_eq!(region.vm_flags(), Some(VmFlags::empty()));
+    assert_eq!(
+      region.max_protection(),
+      Some(Protection::READ_WRITE_EXECUTE)
+    );
+
+    let usage = region.usage().unwrap();
+    assert_eq!(usage.rss(), 4096);
+    assert_eq!(usage.pss(), 2048);
+    assert_eq!(usage.shared_clean(), 4096);
+    assert_eq!(usage.anonymous(), 0);
+
+    let region = iter.next().unwrap().unwrap();
+    assert_eq!(region.vm_flags(), None);
+    assert_eq!(region.max_protection(), None);
+
+    let usage = region.usage().unwrap();
+    assert_eq!(usage.rss(), 8192);
+    assert_eq!(usage.anonymous(), 8192);
+    assert!(iter.next().is_none());
+  }
+
+  #[test]
+  fn smaps_vm_flags_are_parsed() {
+    assert_eq!(parse_smaps_vm_flags(""), VmFlags::empty());
+    assert_eq!(parse_smaps_vm_flags("rd wr mr mw me ac"), VmFlags::ACCOUNT);
+    assert_eq!(
+      parse_smaps_vm_flags("rd wr mr mw me gd ac lo dd"),
+      VmFlags::GROWSDOWN | VmFlags::ACCOUNT | VmFlags::LOCKED | VmFlags::DONTDUMP
+    );
+    assert_eq!(
+      parse_smaps_vm_flags("rd zz hg sl"),
+      VmFlags::HUGEPAGE | VmFlags::SEALED
+    );
+  }
+
+  #[test]
+  fn smaps_max_protection_is_parsed() {
+    assert_eq!(parse_smaps_max_protection(""), Protection::NONE);
+    assert_eq!(
+      parse_smaps_max_protection("rd mr me ac"),
+      Protection::READ_EXECUTE
+    );
+    assert_eq!(
+      parse_smaps_max_protection("rd wr mr mw me"),
+      Protection::READ_WRITE_EXECUTE
+    );
+  }
+
+  #[test]
+  fn procmap_query_matches_procfs_text() -> Result<()> {
+    let file = File::open("/proc/self/maps").map_err(Error::SystemCall)?;
+    if !procmap_query_is_supported(&file) {
+      return Ok(());
+    }
+
+    let memory = crate::alloc(crate::page::size() * 3, Protection::READ_WRITE)?;
+    unsafe {
+      crate::protect(memory.as_ptr::<u8>(), 1, Protection::READ)?;
+    }
+
+    let addresses = [
+      memory.as_ptr::<()>() as usize,
+      memory.as_range().end - 1,
+      smaps_regions_are_parsed as *const () as usize,
+    ];
+
+    let mut name = vec![0u8; libc::PATH_MAX as usize];
+    for address in addresses.iter() {
+      let ioctl = procmap_query(&file, &mut name, *address)?.unwrap();
+      let text = Source::read(File::open("/proc/self/maps").map_err(Error::SystemCall)?)?;
+      let text = QueryIter {
+        source: text,
+        lower_bound: *address,
+        upper_bound: usize::MAX,
+      }
+      .next()
+      .unwrap()?;
+
+      assert_eq!(ioctl, text);
+    }
+    Ok(())
   }
 }
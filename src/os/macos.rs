@@ -19,6 +19,15 @@ impl QueryIter {
   }
 }
 
+pub fn flush_instruction_cache(base: *const (), size: usize) -> Result<()> {
+  extern "C" {
+    fn sys_icache_invalidate(start: *mut libc::c_void, len: libc::size_t);
+  }
+
+  unsafe { sys_icache_invalidate(base as *mut _, size) };
+  Ok(())
+}
+
 impl Iterator for QueryIter {
   type Item = Result<Region>;
 
@@ -64,8 +73,8 @@ impl Iterator for QueryIter {
         let region = Region {
           base: self.region_address as *const _,
           guarded: (info.user_tag == mach2::vm_statistics::VM_MEMORY_GUARD),
-          protection: Protection::from_native(info.protection),
-          max_protection: Protection::from_native(info.max_protection),
+          protection: Protection::from_vm_prot(info.protection),
+          max_protection: Some(Protection::from_vm_prot(info.max_protection)),
           shared: SHARE_MODES.contains(&info.share_mode),
           size: region_size as usize,
           ..Default::default()
@@ -80,7 +89,7 @@ impl Iterator for QueryIter {
 }
 
 impl Protection {
-  fn from_native(protection: vm_prot_t) -> Self {
+  fn from_vm_prot(protection: vm_prot_t) -> Self {
     const MAPPINGS: &[(vm_prot_t, Protection)] = &[
       (VM_PROT_READ, Protection::READ),
       (VM_PROT_WRITE, Protection::WRITE),
@@ -103,9 +112,12 @@ mod tests {
     let rw = VM_PROT_READ | VM_PROT_WRITE;
     let rwx = rw | VM_PROT_EXECUTE;
 
-    assert_eq!(Protection::from_native(0), Protection::NONE);
-    assert_eq!(Protection::from_native(VM_PROT_READ), Protection::READ);
-    assert_eq!(Protection::from_native(rw), Protection::READ_WRITE);
-    assert_eq!(Protection::from_native(rwx), Protection::READ_WRITE_EXECUTE);
+    assert_eq!(Protection::from_vm_prot(0), Protection::NONE);
+    assert_eq!(Protection::from_vm_prot(VM_PROT_READ), Protection::READ);
+    assert_eq!(Protection::from_vm_prot(rw), Protection::READ_WRITE);
+    assert_eq!(
+      Protection::from_vm_prot(rwx),
+      Protection::READ_WRITE_EXECUTE
+    );
   }
 }
@@ -45,8 +45,10 @@ impl Iterator for QueryIter {
     self.vmmap_index += 1;
     Some(Ok(Region {
       base: entry.kve_start as *const _,
-      protection: Protection::from_native(entry.kve_protection as i32),
-      max_protection: Protection::from_native(entry.kve_max_protection as i32),
+      protection: Protection::from_kve_protection(entry.kve_protection as i32),
+      max_protection: Some(Protection::from_kve_protection(
+        entry.kve_max_protection as i32,
+      )),
       shared: (entry.kve_flags & KVME_FLAG_COW as u32) == 0,
       size: (entry.kve_end - entry.kve_start) as _,
       ..Default::default()
@@ -61,7 +63,7 @@ impl Drop for QueryIter {
 }
 
 impl Protection {
-  fn from_native(protection: c_int) -> Self {
+  fn from_kve_protection(protection: c_int) -> Self {
     const MAPPINGS: &[(c_int, Protection)] = &[
       (KVME_PROT_READ, Protection::READ),
       (KVME_PROT_WRITE, Protection::WRITE),
@@ -120,9 +122,15 @@ mod tests {
     let rw = KVME_PROT_READ | KVME_PROT_WRITE;
     let rwx = rw | KVME_PROT_EXEC;
 
-    assert_eq!(Protection::from_native(0), Protection::NONE);
-    assert_eq!(Protection::from_native(KVME_PROT_READ), Protection::READ);
-    assert_eq!(Protection::from_native(rw), Protection::READ_WRITE);
-    assert_eq!(Protection::from_native(rwx), Protection::READ_WRITE_EXECUTE);
+    assert_eq!(Protection::from_kve_protection(0), Protection::NONE);
+    assert_eq!(
+      Protection::from_kve_protection(KVME_PROT_READ),
+      Protection::READ
+    );
+    assert_eq!(Protection::from_kve_protection(rw), Protection::READ_WRITE);
+    assert_eq!(
+      Protection::from_kve_protection(rwx),
+      Protection::READ_WRITE_EXECUTE
+    );
   }
 }
@@ -57,8 +57,10 @@ impl Iterator for QueryIter {
 
     let region = Region {
       base: self.vmentry.kve_start as *const _,
-      protection: Protection::from_native(self.vmentry.kve_protection),
-      max_protection: Protection::from_native(self.vmentry.kve_max_protection),
+      protection: Protection::from_kve_protection(self.vmentry.kve_protection),
+      max_protection: Some(Protection::from_kve_protection(
+        self.vmentry.kve_max_protection,
+      )),
       shared: (self.vmentry.kve_etype & KVE_ET_COPYONWRITE) == 0,
       size: (self.vmentry.kve_end - self.vmentry.kve_start) as _,
       ..Default::default()
@@ -74,7 +76,7 @@ impl Iterator for QueryIter {
 }
 
 impl Protection {
-  fn from_native(protection: c_int) -> Self {
+  fn from_kve_protection(protection: c_int) -> Self {
     const MAPPINGS: &[(c_int, Protection)] = &[
       (KVE_PROT_READ, Protection::READ),
       (KVE_PROT_WRITE, Protection::WRITE),
@@ -121,9 +123,15 @@ mod tests {
     let rw = KVE_PROT_READ | KVE_PROT_WRITE;
     let rwx = rw | KVE_PROT_EXEC;
 
-    assert_eq!(Protection::from_native(0), Protection::NONE);
-    assert_eq!(Protection::from_native(KVE_PROT_READ), Protection::READ);
-    assert_eq!(Protection::from_native(rw), Protection::READ_WRITE);
-    assert_eq!(Protection::from_native(rwx), Protection::READ_WRITE_EXECUTE);
+    assert_eq!(Protection::from_kve_protection(0), Protection::NONE);
+    assert_eq!(
+      Protection::from_kve_protection(KVE_PROT_READ),
+      Protection::READ
+    );
+    assert_eq!(Protection::from_kve_protection(rw), Protection::READ_WRITE);
+    assert_eq!(
+      Protection::from_kve_protection(rwx),
+      Protection::READ_WRITE_EXECUTE
+    );
   }
 }
@@ -1,13 +1,144 @@
-use crate::{Error, Protection, Result};
-use libc::{MAP_ANON, MAP_FAILED, MAP_FIXED, MAP_PRIVATE};
+use crate::util::RangeSet;
+use crate::{Advice, AllocOptions, Error, Protection, Result};
+use libc::{c_int, MAP_ANON, MAP_FAILED, MAP_FIXED, MAP_PRIVATE, MAP_SHARED};
 use libc::{PROT_EXEC, PROT_READ, PROT_WRITE};
+use std::convert::TryFrom;
+use std::fs::File;
 use std::io;
+use std::ops::Range;
+use std::os::unix::io::AsRawFd;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::Mutex;
 
 pub fn page_size() -> usize {
   unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
 }
 
-pub unsafe fn alloc(base: *const (), size: usize, protection: Protection) -> Result<*const ()> {
+pub unsafe fn alloc(
+  base: *const (),
+  size: usize,
+  protection: Protection,
+  options: &AllocOptions,
+) -> Result<*const ()> {
+  let mut flags = alloc_flags(options)?;
+
+  if !base.is_null() {
+    flags |= MAP_FIXED;
+  }
+
+  map(base, size, protection, flags, -1, 0)
+}
+
+pub unsafe fn reserve(size: usize) -> Result<*const ()> {
+  let flags = MAP_PRIVATE | MAP_ANON | MAP_NORESERVE.unwrap_or(0);
+  let base = map(std::ptr::null(), size, Protection::NONE, flags, -1, 0)?;
+
+  uncommitted().lock().unwrap().insert(range(base, size));
+  Ok(base)
+}
+
+pub unsafe fn commit(base: *const (), size: usize, protection: Protection) -> Result<()> {
+  let mut uncommitted = uncommitted().lock().unwrap();
+  let flags = MAP_PRIVATE | MAP_ANON | MAP_FIXED;
+
+  // Uncommitted pages are replaced with accounted memory, whilst pages that are
+  // already committed retain their contents.
+  for pages in uncommitted.overlapping(range(base, size)) {
+    let start = pages.start.max(base as usize);
+    let end = pages.end.min(base as usize + size);
+    map(start as *const (), end - start, protection, flags, -1, 0)?;
+  }
+
+  uncommitted.remove(range(base, size));
+  protect(base, size, protection)
+}
+
+pub unsafe fn decommit(base: *const (), size: usize) -> Result<()> {
+  let mut uncommitted = uncommitted().lock().unwrap();
+  let flags = MAP_PRIVATE | MAP_ANON | MAP_FIXED | MAP_NORESERVE.unwrap_or(0);
+
+  // Remapping the pages discards their contents and releases their accounting
+  map(base, size, Protection::NONE, flags, -1, 0)?;
+  uncommitted.insert(range(base, size));
+  Ok(())
+}
+
+pub unsafe fn release(base: *const (), size: usize) -> Result<()> {
+  let mut uncommitted = uncommitted().lock().unwrap();
+  free(base, size)?;
+  uncommitted.remove(range(base, size));
+  Ok(())
+}
+
+/// Returns whether an address range is part of a reservation, without having
+/// been committed.
+pub fn is_uncommitted(range: &Range<usize>) -> bool {
+  uncommitted().lock().unwrap().contains(range)
+}
+
+/// Returns the process-wide set of reserved, but uncommitted, address ranges.
+fn uncommitted() -> &'static Mutex<RangeSet> {
+  // A `static` mutex cannot be constructed on the minimum supported Rust
+  // version, thus it's lazily allocated, and lives for the process' lifetime.
+  static RANGES: AtomicPtr<Mutex<RangeSet>> = AtomicPtr::new(std::ptr::null_mut());
+
+  let mut ranges = RANGES.load(Ordering::Acquire);
+
+  if ranges.is_null() {
+    let new = Box::into_raw(Box::default());
+    ranges = match RANGES.compare_exchange(ranges, new, Ordering::AcqRel, Ordering::Acquire) {
+      Ok(_) => new,
+      Err(existing) => {
+        // Another thread won the race, thus its set is used instead
+        drop(unsafe { Box::from_raw(new) });
+        existing
+      }
+    };
+  }
+
+  unsafe { &*ranges }
+}
+
+fn range(base: *const (), size: usize) -> Range<usize> {
+  (base as usize)..(base as usize).saturating_add(size)
+}
+
+pub unsafe fn map_file(
+  file: &File,
+  offset: u64,
+  size: usize,
+  protection: Protection,
+  shared: bool,
+) -> Result<*const ()> {
+  let offset = libc::off_t::try_from(offset).map_err(|_| Error::InvalidParameter("offset"))?;
+  let flags = if shared { MAP_SHARED } else { MAP_PRIVATE };
+  map(
+    std::ptr::null(),
+    size,
+    protection,
+    flags,
+    file.as_raw_fd(),
+    offset,
+  )
+}
+
+pub fn flush(base: *const (), size: usize, sync: bool) -> Result<()> {
+  let flags = if sync { libc::MS_SYNC } else { libc::MS_ASYNC };
+
+  match unsafe { libc::msync(base as *mut _, size, flags) } {
+    0 => Ok(()),
+    _ => Err(Error::SystemCall(io::Error::last_os_error())),
+  }
+}
+
+unsafe fn map(
+  base: *const (),
+  size: usize,
+  protection: Protection,
+  flags: c_int,
+  fd: c_int,
+  offset: libc::off_t,
+) -> Result<*const ()> {
   let mut native_prot = protection.to_native();
 
   // This adjustment ensures that the behavior of memory allocation is
@@ -18,27 +149,122 @@ pub unsafe fn alloc(base: *const (), size: usize, protection: Protection) -> Res
     native_prot |= max_protection;
   }
 
-  let mut flags = MAP_PRIVATE | MAP_ANON;
-
-  if !base.is_null() {
-    flags |= MAP_FIXED;
-  }
-
   #[cfg(all(target_vendor = "apple", target_arch = "aarch64"))]
-  if matches!(
-    protection,
-    Protection::WRITE_EXECUTE | Protection::READ_WRITE_EXECUTE
-  ) {
+  let flags = if fd == -1
+    && matches!(
+      protection,
+      Protection::WRITE_EXECUTE | Protection::READ_WRITE_EXECUTE
+    ) {
     // On hardened context, MAP_JIT is necessary (on arm64) to allow W/X'ed regions.
-    flags |= libc::MAP_JIT;
-  }
+    flags | libc::MAP_JIT
+  } else {
+    flags
+  };
 
-  match libc::mmap(base as *mut _, size, native_prot, flags, -1, 0) {
+  match libc::mmap(base as *mut _, size, native_prot, flags, fd, offset) {
     MAP_FAILED => Err(Error::SystemCall(io::Error::last_os_error())),
     address => Ok(address as *const ()),
   }
 }
 
+/// Translates allocation options to their native `mmap` flags.
+fn alloc_flags(options: &AllocOptions) -> Result<c_int> {
+  let mut flags = MAP_ANON
+    | if options.shared {
+      MAP_SHARED
+    } else {
+      MAP_PRIVATE
+    };
+
+  // Populating memory is merely an optimization, thus it's ignored if unsupported
+  if options.populate {
+    flags |= MAP_POPULATE.unwrap_or(0);
+  }
+
+  // Stack memory is merely a hint on most platforms, thus it's ignored if
+  // unsupported (on OpenBSD it's required for memory used as a stack).
+  if options.stack {
+    flags |= MAP_STACK.unwrap_or(0);
+  }
+
+  if options.no_reserve {
+    flags |= MAP_NORESERVE.ok_or(Error::Unsupported("no_reserve"))?;
+  }
+
+  if options.grows_down {
+    flags |= MAP_GROWSDOWN.ok_or(Error::Unsupported("grows_down"))?;
+  }
+
+  if options.map_32bit {
+    flags |= MAP_32BIT.ok_or(Error::Unsupported("map_32bit"))?;
+  }
+
+  if let Some(page_size) = options.huge_page_size {
+    let (hugetlb, shift) = MAP_HUGETLB.ok_or(Error::Unsupported("huge_page_size"))?;
+    flags |= hugetlb | ((page_size.trailing_zeros() as c_int) << shift);
+  }
+
+  Ok(flags)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const MAP_POPULATE: Option<c_int> = Some(libc::MAP_POPULATE);
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const MAP_POPULATE: Option<c_int> = None;
+
+#[cfg(any(
+  target_os = "linux",
+  target_os = "android",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+const MAP_STACK: Option<c_int> = Some(libc::MAP_STACK);
+#[cfg(not(any(
+  target_os = "linux",
+  target_os = "android",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+const MAP_STACK: Option<c_int> = None;
+
+#[cfg(any(
+  target_os = "linux",
+  target_os = "android",
+  target_os = "illumos",
+  target_os = "netbsd"
+))]
+const MAP_NORESERVE: Option<c_int> = Some(libc::MAP_NORESERVE);
+#[cfg(not(any(
+  target_os = "linux",
+  target_os = "android",
+  target_os = "illumos",
+  target_os = "netbsd"
+)))]
+const MAP_NORESERVE: Option<c_int> = None;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const MAP_GROWSDOWN: Option<c_int> = Some(libc::MAP_GROWSDOWN);
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const MAP_GROWSDOWN: Option<c_int> = None;
+
+#[cfg(all(
+  any(target_os = "linux", target_os = "android", target_os = "freebsd"),
+  target_arch = "x86_64"
+))]
+const MAP_32BIT: Option<c_int> = Some(libc::MAP_32BIT);
+#[cfg(not(all(
+  any(target_os = "linux", target_os = "android", target_os = "freebsd"),
+  target_arch = "x86_64"
+)))]
+const MAP_32BIT: Option<c_int> = None;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const MAP_HUGETLB: Option<(c_int, c_int)> = Some((libc::MAP_HUGETLB, libc::MAP_HUGE_SHIFT));
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const MAP_HUGETLB: Option<(c_int, c_int)> = None;
+
 pub unsafe fn free(base: *const (), size: usize) -> Result<()> {
   match libc::munmap(base as *mut _, size) {
     0 => Ok(()),
@@ -46,11 +272,62 @@ pub unsafe fn free(base: *const (), size: usize) -> Result<()> {
   }
 }
 
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub unsafe fn resize(
+  base: *const (),
+  size: usize,
+  new_size: usize,
+  _may_move: bool,
+) -> Result<*const ()> {
+  // Without `mremap`, only the tail of a mapping can be released
+  if new_size > size {
+    return Err(Error::Unsupported("resize"));
+  }
+
+  if new_size < size {
+    free((base as usize + new_size) as *const (), size - new_size)?;
+  }
+
+  Ok(base)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub unsafe fn move_to(_base: *const (), _size: usize, _address: *const ()) -> Result<()> {
+  Err(Error::Unsupported("move_to"))
+}
+
 pub unsafe fn protect(base: *const (), size: usize, protection: Protection) -> Result<()> {
-  match libc::mprotect(base as *mut _, size, protection.to_native()) {
-    0 => Ok(()),
-    _ => Err(Error::SystemCall(io::Error::last_os_error())),
+  if libc::mprotect(base as *mut _, size, protection.to_native()) == 0 {
+    return Ok(());
+  }
+
+  let error = io::Error::last_os_error();
+
+  // The maximum protection is only queried once the call has failed, to avoid
+  // the overhead of a query for each successful protection change.
+  if error.raw_os_error() == Some(libc::EACCES) {
+    let requested = protection & Protection::READ_WRITE_EXECUTE;
+
+    match max_protection(base, size) {
+      Some(max) if !max.contains(requested) => return Err(Error::MaxProtectionExceeded(max)),
+      _ => (),
+    }
   }
+
+  Err(Error::SystemCall(error))
+}
+
+/// Returns the maximum protection shared by all regions within a range, if
+/// known.
+fn max_protection(base: *const (), size: usize) -> Option<Protection> {
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  let regions = crate::query_range_detailed(base, size).ok()?;
+  #[cfg(not(any(target_os = "linux", target_os = "android")))]
+  let regions = crate::query_range(base, size).ok()?;
+
+  regions
+    .filter_map(|region| region.ok()?.max_protection())
+    .reduce(|acc, max| acc & max)
 }
 
 pub fn lock(base: *const (), size: usize) -> Result<()> {
@@ -67,11 +344,115 @@ pub fn unlock(base: *const (), size: usize) -> Result<()> {
   }
 }
 
+#[cfg(not(target_os = "openbsd"))]
+pub fn resident(base: *const (), size: usize, pages: &mut [u8]) -> Result<()> {
+  match unsafe { libc::mincore(base as _, size, pages.as_mut_ptr().cast()) } {
+    0 => Ok(()),
+    _ => match io::Error::last_os_error() {
+      error if error.raw_os_error() == Some(libc::ENOMEM) => Err(Error::UnmappedRegion),
+      error => Err(Error::SystemCall(error)),
+    },
+  }
+}
+
+#[cfg(all(
+  not(any(target_os = "macos", target_os = "ios")),
+  not(any(target_arch = "x86", target_arch = "x86_64"))
+))]
+pub fn flush_instruction_cache(base: *const (), size: usize) -> Result<()> {
+  extern "C" {
+    fn __clear_cache(start: *mut libc::c_char, end: *mut libc::c_char);
+  }
+
+  let start = base as *mut libc::c_char;
+  unsafe { __clear_cache(start, start.wrapping_add(size)) };
+  Ok(())
+}
+
+#[cfg(all(
+  not(any(target_os = "macos", target_os = "ios")),
+  any(target_arch = "x86", target_arch = "x86_64")
+))]
+pub fn flush_instruction_cache(_base: *const (), _size: usize) -> Result<()> {
+  // The instruction cache is coherent with the data cache on x86
+  Ok(())
+}
+
+pub unsafe fn advise(base: *const (), size: usize, advice: Advice) -> Result<()> {
+  let native = advice
+    .to_native()
+    .ok_or_else(|| Error::Unsupported(advice.name()))?;
+
+  match libc::madvise(base as *mut _, size, native) {
+    0 => Ok(()),
+    _ => match io::Error::last_os_error() {
+      // Kernels that predate an (OS-specific) advice reject it as invalid
+      error if error.raw_os_error() == Some(libc::EINVAL) && !advice.is_standard() => {
+        Err(Error::Unsupported(advice.name()))
+      }
+      error => Err(Error::SystemCall(error)),
+    },
+  }
+}
+
+impl Advice {
+  fn to_native(self) -> Option<c_int> {
+    match self {
+      Advice::Normal => Some(libc::MADV_NORMAL),
+      Advice::Random => Some(libc::MADV_RANDOM),
+      Advice::Sequential => Some(libc::MADV_SEQUENTIAL),
+      Advice::WillNeed => Some(libc::MADV_WILLNEED),
+      Advice::DontNeed => Some(libc::MADV_DONTNEED),
+      Advice::Free => Some(libc::MADV_FREE),
+      #[cfg(target_os = "freebsd")]
+      Advice::DontDump => Some(libc::MADV_NOCORE),
+      #[cfg(target_os = "freebsd")]
+      Advice::DoDump => Some(libc::MADV_CORE),
+      #[cfg(any(target_os = "linux", target_os = "android"))]
+      advice => advice.to_linux_native(),
+      #[cfg(not(any(target_os = "linux", target_os = "android")))]
+      _ => None,
+    }
+  }
+
+  /// Returns whether the advice is specified by POSIX (and thus universally
+  /// supported).
+  fn is_standard(self) -> bool {
+    matches!(
+      self,
+      Advice::Normal | Advice::Random | Advice::Sequential | Advice::WillNeed | Advice::DontNeed
+    )
+  }
+}
+
 impl Protection {
-  fn to_native(self) -> libc::c_int {
+  /// Converts native `PROT_*` flags (as used by `mmap` and `mprotect`) to a
+  /// protection.
+  ///
+  /// The flags are expected as the `c_int` used by `libc` (i.e. `i32` on all
+  /// supported platforms), and flags that are unknown to this crate are
+  /// discarded.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use region::Protection;
+  ///
+  /// let protection = Protection::from_native(libc::PROT_READ | libc::PROT_WRITE);
+  /// assert_eq!(protection, Protection::READ_WRITE);
+  /// ```
+  #[inline]
+  pub fn from_native(protection: i32) -> Self {
+    Protection::from_bits_truncate(protection as usize)
+  }
+
+  /// Converts the protection to native `PROT_*` flags (as used by `mmap` and
+  /// `mprotect`).
+  #[inline]
+  pub fn to_native(self) -> i32 {
     // This is directly mapped to its native counterpart to allow users to
     // include non-standard flags with `Protection::from_bits_unchecked`.
-    self.bits as libc::c_int
+    self.bits as i32
   }
 }
 
@@ -100,4 +481,36 @@ mod tests {
     assert_eq!(Protection::READ_WRITE.to_native(), PROT_READ | PROT_WRITE);
     assert_eq!(Protection::READ_WRITE_EXECUTE.to_native(), rwx);
   }
+
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn protection_extensions_match_linux_constants() {
+    assert_eq!(Protection::GROWSDOWN.to_native(), libc::PROT_GROWSDOWN);
+    assert_eq!(Protection::GROWSUP.to_native(), libc::PROT_GROWSUP);
+
+    // The `libc` crate does not define `PROT_SEM` (nor `PROT_BTI` & `PROT_MTE`
+    // for musl), thus the kernel's values are used
+    let prot_sem = if cfg!(any(target_arch = "mips", target_arch = "mips64")) {
+      0x10
+    } else {
+      0x8
+    };
+    assert_eq!(Protection::SEM.to_native(), prot_sem);
+
+    #[cfg(target_arch = "aarch64")]
+    {
+      assert_eq!(Protection::BTI.to_native(), 0x10);
+      assert_eq!(Protection::MTE.to_native(), 0x20);
+    }
+  }
+
+  #[test]
+  fn protection_flags_are_mapped_from_native() {
+    let rwx = PROT_READ | PROT_WRITE | PROT_EXEC;
+
+    assert_eq!(Protection::from_native(PROT_NONE), Protection::NONE);
+    assert_eq!(Protection::from_native(PROT_READ), Protection::READ);
+    assert_eq!(Protection::from_native(rwx), Protection::READ_WRITE_EXECUTE);
+    assert_eq!(Protection::from_native(1 << 30), Protection::NONE);
+  }
 }
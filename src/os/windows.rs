@@ -1,16 +1,24 @@
-use crate::{Error, Protection, Region, Result};
+use crate::{Advice, AllocOptions, Error, Protection, Region, Result};
 use std::cmp::{max, min};
 use std::ffi::c_void;
+use std::fs::File;
 use std::io;
 use std::mem::{size_of, MaybeUninit};
+use std::os::windows::io::AsRawHandle;
 use std::sync::Once;
+use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+use windows_sys::Win32::System::Diagnostics::Debug::FlushInstructionCache;
 use windows_sys::Win32::System::Memory::{
-  VirtualAlloc, VirtualFree, VirtualLock, VirtualProtect, VirtualQuery, VirtualUnlock,
-  MEMORY_BASIC_INFORMATION, MEM_COMMIT, MEM_PRIVATE, MEM_RELEASE, MEM_RESERVE, PAGE_EXECUTE,
-  PAGE_EXECUTE_READ, PAGE_EXECUTE_READWRITE, PAGE_EXECUTE_WRITECOPY, PAGE_GUARD, PAGE_NOACCESS,
-  PAGE_NOCACHE, PAGE_READONLY, PAGE_READWRITE, PAGE_WRITECOMBINE, PAGE_WRITECOPY,
+  CreateFileMappingW, DiscardVirtualMemory, FlushViewOfFile, MapViewOfFile, PrefetchVirtualMemory,
+  UnmapViewOfFile, VirtualAlloc, VirtualFree, VirtualLock, VirtualProtect, VirtualQuery,
+  VirtualUnlock, FILE_MAP_COPY, FILE_MAP_EXECUTE, FILE_MAP_READ, FILE_MAP_WRITE,
+  MEMORY_BASIC_INFORMATION, MEMORY_MAPPED_VIEW_ADDRESS, MEM_COMMIT, MEM_DECOMMIT, MEM_MAPPED,
+  MEM_PRIVATE, MEM_RELEASE, MEM_RESERVE, PAGE_EXECUTE, PAGE_EXECUTE_READ, PAGE_EXECUTE_READWRITE,
+  PAGE_EXECUTE_WRITECOPY, PAGE_GUARD, PAGE_NOACCESS, PAGE_NOCACHE, PAGE_READONLY, PAGE_READWRITE,
+  PAGE_WRITECOMBINE, PAGE_WRITECOPY, WIN32_MEMORY_RANGE_ENTRY,
 };
 use windows_sys::Win32::System::SystemInformation::{GetNativeSystemInfo, SYSTEM_INFO};
+use windows_sys::Win32::System::Threading::GetCurrentProcess;
 
 pub struct QueryIter {
   region_address: usize,
@@ -83,7 +91,25 @@ pub fn page_size() -> usize {
   system_info().dwPageSize as usize
 }
 
-pub unsafe fn alloc(base: *const (), size: usize, protection: Protection) -> Result<*const ()> {
+pub unsafe fn alloc(
+  base: *const (),
+  size: usize,
+  protection: Protection,
+  options: &AllocOptions,
+) -> Result<*const ()> {
+  // Populating memory and stack memory are merely hints, thus they're ignored
+  let unsupported = [
+    (options.shared, "shared"),
+    (options.no_reserve, "no_reserve"),
+    (options.grows_down, "grows_down"),
+    (options.map_32bit, "map_32bit"),
+    (options.huge_page_size.is_some(), "huge_page_size"),
+  ];
+
+  if let Some((_, option)) = unsupported.iter().find(|(enabled, _)| *enabled) {
+    return Err(Error::Unsupported(*option));
+  }
+
   let allocation = VirtualAlloc(
     base as *mut c_void,
     size,
@@ -99,12 +125,170 @@ pub unsafe fn alloc(base: *const (), size: usize, protection: Protection) -> Res
 }
 
 pub unsafe fn free(base: *const (), _size: usize) -> Result<()> {
+  let mut info: MEMORY_BASIC_INFORMATION = std::mem::zeroed();
+  let bytes = VirtualQuery(
+    base as *const c_void,
+    &mut info,
+    size_of::<MEMORY_BASIC_INFORMATION>(),
+  );
+
+  // File mappings are views, which cannot be released using `VirtualFree`
+  if bytes != 0 && info.Type == MEM_MAPPED {
+    let view = MEMORY_MAPPED_VIEW_ADDRESS {
+      Value: base as *mut c_void,
+    };
+
+    return match UnmapViewOfFile(view) {
+      0 => Err(Error::SystemCall(io::Error::last_os_error())),
+      _ => Ok(()),
+    };
+  }
+
   match VirtualFree(base as *mut c_void, 0, MEM_RELEASE) {
     0 => Err(Error::SystemCall(io::Error::last_os_error())),
     _ => Ok(()),
   }
 }
 
+pub unsafe fn reserve(size: usize) -> Result<*const ()> {
+  match VirtualAlloc(std::ptr::null(), size, MEM_RESERVE, PAGE_NOACCESS) {
+    allocation if allocation.is_null() => Err(Error::SystemCall(io::Error::last_os_error())),
+    allocation => Ok(allocation as *const ()),
+  }
+}
+
+pub unsafe fn commit(base: *const (), size: usize, protection: Protection) -> Result<()> {
+  let allocation = VirtualAlloc(
+    base as *mut c_void,
+    size,
+    MEM_COMMIT,
+    protection.to_native(),
+  );
+
+  if allocation.is_null() {
+    Err(Error::SystemCall(io::Error::last_os_error()))
+  } else {
+    Ok(())
+  }
+}
+
+pub unsafe fn decommit(base: *const (), size: usize) -> Result<()> {
+  match VirtualFree(base as *mut c_void, size, MEM_DECOMMIT) {
+    0 => Err(Error::SystemCall(io::Error::last_os_error())),
+    _ => Ok(()),
+  }
+}
+
+pub unsafe fn release(base: *const (), size: usize) -> Result<()> {
+  free(base, size)
+}
+
+pub fn flush_instruction_cache(base: *const (), size: usize) -> Result<()> {
+  match unsafe { FlushInstructionCache(GetCurrentProcess(), base as *const c_void, size) } {
+    0 => Err(Error::SystemCall(io::Error::last_os_error())),
+    _ => Ok(()),
+  }
+}
+
+pub unsafe fn advise(base: *const (), size: usize, advice: Advice) -> Result<()> {
+  match advice {
+    Advice::WillNeed => {
+      let entry = WIN32_MEMORY_RANGE_ENTRY {
+        VirtualAddress: base as *mut c_void,
+        NumberOfBytes: size,
+      };
+
+      match PrefetchVirtualMemory(GetCurrentProcess(), 1, &entry, 0) {
+        0 => Err(Error::SystemCall(io::Error::last_os_error())),
+        _ => Ok(()),
+      }
+    }
+    Advice::Free => match DiscardVirtualMemory(base as *mut c_void, size) {
+      0 => Ok(()),
+      code => Err(Error::SystemCall(io::Error::from_raw_os_error(code as i32))),
+    },
+    _ => Err(Error::Unsupported(advice.name())),
+  }
+}
+
+pub unsafe fn map_file(
+  file: &File,
+  offset: u64,
+  size: usize,
+  protection: Protection,
+  shared: bool,
+) -> Result<*const ()> {
+  // Views must be aligned to the allocation granularity, not the page size
+  if offset % u64::from(system_info().dwAllocationGranularity) != 0 {
+    return Err(Error::InvalidParameter("offset"));
+  }
+
+  let writable = protection.contains(Protection::WRITE);
+  let executable = protection.contains(Protection::EXECUTE);
+
+  let (page_protection, access) = match (writable, shared, executable) {
+    (false, _, false) => (PAGE_READONLY, FILE_MAP_READ),
+    (false, _, true) => (PAGE_EXECUTE_READ, FILE_MAP_READ | FILE_MAP_EXECUTE),
+    (true, true, false) => (PAGE_READWRITE, FILE_MAP_WRITE),
+    (true, true, true) => (PAGE_EXECUTE_READWRITE, FILE_MAP_WRITE | FILE_MAP_EXECUTE),
+    (true, false, false) => (PAGE_WRITECOPY, FILE_MAP_COPY),
+    (true, false, true) => (PAGE_EXECUTE_WRITECOPY, FILE_MAP_COPY | FILE_MAP_EXECUTE),
+  };
+
+  let mapping = CreateFileMappingW(
+    file.as_raw_handle() as HANDLE,
+    std::ptr::null(),
+    page_protection,
+    0,
+    0,
+    std::ptr::null(),
+  );
+
+  if mapping == 0 {
+    return Err(Error::SystemCall(io::Error::last_os_error()));
+  }
+
+  // The view retains a reference to the mapping, thus it can be closed directly
+  let view = MapViewOfFile(mapping, access, (offset >> 32) as u32, offset as u32, size);
+  let error = io::Error::last_os_error();
+  CloseHandle(mapping);
+
+  if view.Value.is_null() {
+    return Err(Error::SystemCall(error));
+  }
+
+  // File mappings cannot be created without any access
+  if protection == Protection::NONE {
+    if let Err(error) = protect(view.Value as *const (), size, protection) {
+      UnmapViewOfFile(view);
+      return Err(error);
+    }
+  }
+
+  Ok(view.Value as *const ())
+}
+
+pub fn flush(base: *const (), size: usize, _sync: bool) -> Result<()> {
+  // Waiting for the writes requires the file's handle, which is not retained
+  match unsafe { FlushViewOfFile(base as *const c_void, size) } {
+    0 => Err(Error::SystemCall(io::Error::last_os_error())),
+    _ => Ok(()),
+  }
+}
+
+pub unsafe fn resize(
+  _base: *const (),
+  _size: usize,
+  _new_size: usize,
+  _may_move: bool,
+) -> Result<*const ()> {
+  Err(Error::Unsupported("resize"))
+}
+
+pub unsafe fn move_to(_base: *const (), _size: usize, _address: *const ()) -> Result<()> {
+  Err(Error::Unsupported("move_to"))
+}
+
 pub unsafe fn protect(base: *const (), size: usize, protection: Protection) -> Result<()> {
   let result = VirtualProtect(base as *mut c_void, size, protection.to_native(), &mut 0);
 
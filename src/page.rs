@@ -1,6 +1,8 @@
 //! Page related functions.
 
 use crate::os;
+#[cfg(all(unix, not(target_os = "openbsd")))]
+use crate::{util, Result};
 use std::sync::Once;
 
 /// Returns the operating system's page size.
@@ -58,6 +60,133 @@ pub fn ceil<T>(address: *const T) -> *const T {
   }
 }
 
+/// Returns which pages within an address range are resident in RAM.
+///
+/// Pages that are not resident would cause a page fault if accessed, e.g.
+/// because they have never been touched, or because they have been swapped
+/// out.
+///
+/// # Parameters
+///
+/// - The range is `[address, address + size)`
+/// - The address is rounded down to the closest page boundary.
+/// - The size may not be zero.
+/// - The size is rounded up to the closest page boundary, relative to the
+///   address.
+///
+/// # Errors
+///
+/// - If the range contains unmapped pages,
+///   [`Error::UnmappedRegion`](crate::Error::UnmappedRegion) will be returned.
+/// - If an interaction with the underlying operating system fails, an error
+///   will be returned.
+/// - If size is zero,
+///   [`Error::InvalidParameter`](crate::Error::InvalidParameter) will be
+///   returned.
+///
+/// # Implementation
+///
+/// This function is implemented using `mincore`, and is therefore not available
+/// on Windows nor OpenBSD.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> region::Result<()> {
+/// use region::{page, Protection};
+///
+/// let mut memory = region::alloc(page::size() * 2, Protection::READ_WRITE)?;
+/// unsafe { *memory.as_mut_ptr::<u8>() = 1 };
+///
+/// let residency = page::resident(memory.as_ptr::<u8>(), memory.len())?;
+/// assert_eq!(residency.len(), 2);
+/// assert!(residency.is_resident(0));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(all(unix, not(target_os = "openbsd")))]
+#[inline]
+pub fn resident<T>(address: *const T, size: usize) -> Result<Residency> {
+  let (address, size) = util::round_to_page_boundaries(address, size)?;
+
+  let mut pages = vec![0u8; size / self::size()];
+  os::resident(address.cast(), size, &mut pages)?;
+
+  // Only the least significant bit is defined, the others are reserved
+  Ok(Residency::new(pages.iter().map(|page| page & 1 == 1)))
+}
+
+/// The residency of a range of pages.
+///
+/// This `struct` is created by [`resident`], storing one bit per page.
+#[cfg(all(unix, not(target_os = "openbsd")))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::len_without_is_empty)]
+pub struct Residency {
+  bits: Vec<usize>,
+  len: usize,
+  count: usize,
+}
+
+#[cfg(all(unix, not(target_os = "openbsd")))]
+impl Residency {
+  const BITS: usize = std::mem::size_of::<usize>() * 8;
+
+  fn new<I: Iterator<Item = bool>>(pages: I) -> Self {
+    let mut residency = Self {
+      bits: Vec::new(),
+      len: 0,
+      count: 0,
+    };
+
+    for resident in pages {
+      let (index, bit) = (residency.len / Self::BITS, residency.len % Self::BITS);
+      if bit == 0 {
+        residency.bits.push(0);
+      }
+
+      if resident {
+        residency.bits[index] |= 1 << bit;
+        residency.count += 1;
+      }
+
+      residency.len += 1;
+    }
+
+    residency
+  }
+
+  /// Returns the number of pages in the range.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  /// Returns the number of pages that are resident in RAM.
+  #[inline(always)]
+  pub fn count(&self) -> usize {
+    self.count
+  }
+
+  /// Returns whether a page, identified by its index within the range, is
+  /// resident in RAM.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the index is out of bounds.
+  #[inline]
+  pub fn is_resident(&self, index: usize) -> bool {
+    assert!(index < self.len, "page index out of bounds");
+    self.bits[index / Self::BITS] & (1 << (index % Self::BITS)) != 0
+  }
+
+  /// Returns an iterator over the residency of each page in the range.
+  #[inline]
+  pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+    (0..self.len).map(move |index| self.is_resident(index))
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -74,14 +203,51 @@ mod tests {
   #[test]
   fn page_rounding_works() {
     let pz = size();
+    #[allow(clippy::manual_dangling_ptr)]
     let point = 1 as *const ();
 
     assert_eq!(floor(point) as usize, 0);
     assert_eq!(floor(pz as *const ()) as usize, pz);
-    assert_eq!(floor(usize::max_value() as *const ()) as usize % pz, 0);
+    assert_eq!(floor(usize::MAX as *const ()) as usize % pz, 0);
 
     assert_eq!(ceil(point) as usize, pz);
     assert_eq!(ceil(pz as *const ()) as usize, pz);
-    assert_eq!(ceil(usize::max_value() as *const ()) as usize % pz, 0);
+    assert_eq!(ceil(usize::MAX as *const ()) as usize % pz, 0);
+  }
+
+  #[test]
+  #[cfg(all(unix, not(target_os = "openbsd")))]
+  fn residency_stores_one_bit_per_page() {
+    let pages = (0..130).map(|index| index % 3 == 0);
+    let residency = Residency::new(pages.clone());
+
+    assert_eq!(residency.len(), 130);
+    assert_eq!(residency.count(), 44);
+    assert!(residency.iter().eq(pages));
+  }
+
+  #[test]
+  #[cfg(all(unix, not(target_os = "openbsd")))]
+  fn resident_reports_touched_pages() -> crate::Result<()> {
+    use crate::{alloc, Error, Protection};
+
+    let mut memory = alloc(size() * 4, Protection::READ_WRITE)?;
+    unsafe {
+      *memory.as_mut_ptr::<u8>() = 1;
+      *memory.as_mut_ptr::<u8>().add(size() * 2 + 1) = 1;
+    }
+
+    let residency = resident(memory.as_ptr::<u8>().wrapping_add(1), memory.len() - 1)?;
+    assert_eq!(residency.len(), 4);
+    assert!(residency.is_resident(0));
+    assert!(residency.is_resident(2));
+    if cfg!(any(target_os = "linux", target_os = "android")) {
+      assert_eq!(residency.count(), 2);
+    }
+
+    let base = memory.as_ptr::<u8>();
+    std::mem::drop(memory);
+    assert!(matches!(resident(base, 1), Err(Error::UnmappedRegion)));
+    Ok(())
   }
 }
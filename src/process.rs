@@ -0,0 +1,210 @@
+use crate::{os, util, Error, MemoryUsage, QueryIter, Region, Result};
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// A handle to a process, used for querying its virtual memory.
+///
+/// Where supported by the kernel (Linux 5.3+), the handle is backed by a
+/// `pidfd`. This ensures that a query never describes another process that has
+/// been assigned the same PID after the original process exited; instead the
+/// query fails with `ESRCH`.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> region::Result<()> {
+/// let process = region::Process::from_pid(std::process::id())?;
+///
+/// let data = [0; 100];
+/// let region = process.query(data.as_ptr())?;
+///
+/// assert_eq!(region.protection(), region::Protection::READ_WRITE);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Process {
+  pid: libc::pid_t,
+  pidfd: Option<File>,
+}
+
+impl Process {
+  /// Creates a handle to a process, identified by its PID.
+  ///
+  /// # Errors
+  ///
+  /// - If the process does not exist, an error will be returned.
+  /// - If the PID is zero or does not fit a `pid_t`,
+  ///   [`Error::InvalidParameter`] will be returned.
+  #[inline]
+  pub fn from_pid(pid: u32) -> Result<Self> {
+    let pid = match libc::pid_t::try_from(pid) {
+      Ok(pid) if pid > 0 => pid,
+      _ => return Err(Error::InvalidParameter("pid")),
+    };
+
+    let pidfd = os::pidfd_open(pid)?;
+
+    // Without a pidfd, the process' existence is verified using procfs
+    if pidfd.is_none() && !Path::new(&format!("/proc/{}", pid)).exists() {
+      return Err(Error::SystemCall(io::Error::from_raw_os_error(libc::ESRCH)));
+    }
+
+    Ok(Self { pid, pidfd })
+  }
+
+  /// Returns the process' PID.
+  #[inline(always)]
+  pub fn pid(&self) -> u32 {
+    self.pid as u32
+  }
+
+  /// Queries the process with an address, returning the region it resides
+  /// within.
+  ///
+  /// This is the equivalent of [`query`](crate::query) for another process.
+  ///
+  /// # Errors
+  ///
+  /// - If the process has exited, an error will be returned.
+  /// - If the address is not mapped, [`Error::UnmappedRegion`] will be
+  ///   returned.
+  /// - If the caller is not permitted to inspect the process (see
+  ///   `ptrace(2)`), an error will be returned.
+  #[inline]
+  pub fn query<T>(&self, address: *const T) -> Result<Region> {
+    let (address, size) = util::round_to_page_boundaries(address, 1)?;
+
+    QueryIter::with_options(Some(self), false, address, size)?
+      .next()
+      .ok_or(Error::UnmappedRegion)?
+  }
+
+  /// Queries the process for mapped regions that overlap with the specified
+  /// range.
+  ///
+  /// This is the equivalent of [`query_range`](crate::query_range) for another
+  /// process.
+  ///
+  /// # Errors
+  ///
+  /// - If the process has exited, an error will be returned.
+  /// - If size is zero, [`Error::InvalidParameter`] will be returned.
+  /// - If the caller is not permitted to inspect the process (see
+  ///   `ptrace(2)`), an error will be returned.
+  #[inline]
+  pub fn query_range<T>(&self, address: *const T, size: usize) -> Result<QueryIter> {
+    let (address, size) = util::round_to_page_boundaries(address, size)?;
+    QueryIter::with_options(Some(self), false, address, size)
+  }
+
+  /// Queries the process with an address, returning the region it resides
+  /// within, including its memory usage.
+  ///
+  /// This is the equivalent of [`query_detailed`](crate::query_detailed) for
+  /// another process.
+  ///
+  /// # Errors
+  ///
+  /// See [`Process::query`].
+  #[inline]
+  pub fn query_detailed<T>(&self, address: *const T) -> Result<Region> {
+    let (address, size) = util::round_to_page_boundaries(address, 1)?;
+
+    QueryIter::with_options(Some(self), true, address, size)?
+      .next()
+      .ok_or(Error::UnmappedRegion)?
+  }
+
+  /// Queries the process for mapped regions that overlap with the specified
+  /// range, including their memory usage.
+  ///
+  /// This is the equivalent of
+  /// [`query_range_detailed`](crate::query_range_detailed) for another process.
+  ///
+  /// # Errors
+  ///
+  /// See [`Process::query_range`].
+  #[inline]
+  pub fn query_range_detailed<T>(&self, address: *const T, size: usize) -> Result<QueryIter> {
+    let (address, size) = util::round_to_page_boundaries(address, size)?;
+    QueryIter::with_options(Some(self), true, address, size)
+  }
+
+  /// Queries the memory usage totals of the process.
+  ///
+  /// This is the equivalent of [`query_usage`](crate::query_usage) for another
+  /// process.
+  ///
+  /// # Errors
+  ///
+  /// - If the process has exited, an error will be returned.
+  /// - If the caller is not permitted to inspect the process (see
+  ///   `ptrace(2)`), an error will be returned.
+  #[inline]
+  pub fn query_usage(&self) -> Result<MemoryUsage> {
+    os::query_usage(Some(self))
+  }
+
+  /// Verifies that the process has not exited (and its PID been recycled).
+  pub(crate) fn ensure_alive(&self) -> Result<()> {
+    match self.pidfd {
+      Some(ref pidfd) if !os::pidfd_is_alive(pidfd)? => {
+        Err(Error::SystemCall(io::Error::from_raw_os_error(libc::ESRCH)))
+      }
+      _ => Ok(()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{alloc, page, Protection};
+  use std::process::{Command, Stdio};
+
+  #[test]
+  fn process_rejects_invalid_pid() {
+    assert!(matches!(
+      Process::from_pid(0),
+      Err(Error::InvalidParameter(_))
+    ));
+    assert!(matches!(
+      Process::from_pid(u32::MAX),
+      Err(Error::InvalidParameter(_))
+    ));
+  }
+
+  #[test]
+  fn process_query_matches_current_process() -> Result<()> {
+    let memory = alloc(page::size() * 2, Protection::READ_EXECUTE)?;
+    let process = Process::from_pid(std::process::id())?;
+
+    let region = process.query(memory.as_ptr::<()>())?;
+    assert_eq!(region, crate::query(memory.as_ptr::<()>())?);
+    assert_eq!(region.protection(), Protection::READ_EXECUTE);
+    Ok(())
+  }
+
+  #[test]
+  fn process_can_query_child_process() -> Result<()> {
+    let mut child = Command::new("sleep")
+      .arg("10")
+      .stdout(Stdio::null())
+      .spawn()
+      .map_err(Error::SystemCall)?;
+
+    let process = Process::from_pid(child.id())?;
+    let regions = process
+      .query_range(std::ptr::null::<()>(), usize::MAX)?
+      .collect::<Result<Vec<_>>>();
+
+    child.kill().map_err(Error::SystemCall)?;
+    child.wait().map_err(Error::SystemCall)?;
+
+    assert!(regions?.iter().any(|region| region.is_executable()));
+    assert!(process.query_range(std::ptr::null::<()>(), 1).is_err());
+    Ok(())
+  }
+}
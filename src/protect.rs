@@ -18,10 +18,17 @@ use crate::{os, util, Protection, QueryIter, Region, Result};
 /// # Errors
 ///
 /// - If an interaction with the underlying operating system fails, an error
-/// will be returned.
+///   will be returned.
 /// - If size is zero,
-/// [`Error::InvalidParameter`](crate::Error::InvalidParameter) will be
-/// returned.
+///   [`Error::InvalidParameter`](crate::Error::InvalidParameter) will be
+///   returned.
+/// - If the protection exceeds the maximum protection of the pages (see
+///   [`Region::max_protection`](crate::Region::max_protection)),
+///   [`Error::MaxProtectionExceeded`](crate::Error::MaxProtectionExceeded)
+///   will be returned. This is not checked in advance; the maximum protection
+///   is only queried once the system call has failed with `EACCES`, and only
+///   on platforms that report it (Linux, macOS, NetBSD & OpenBSD). Otherwise,
+///   the system call's error is returned.
 ///
 /// # Safety
 ///
@@ -90,10 +97,10 @@ pub unsafe fn protect<T>(address: *const T, size: usize, protection: Protection)
 /// # Errors
 ///
 /// - If an interaction with the underlying operating system fails, an error
-/// will be returned.
+///   will be returned.
 /// - If size is zero,
-/// [`Error::InvalidParameter`](crate::Error::InvalidParameter) will be
-/// returned.
+///   [`Error::InvalidParameter`](crate::Error::InvalidParameter) will be
+///   returned.
 ///
 /// # Safety
 ///
@@ -176,10 +183,11 @@ mod tests {
   )))]
   fn protect_can_alter_text_segments() {
     #[allow(clippy::ptr_as_ptr)]
-    let address = &mut protect_can_alter_text_segments as *mut _ as *mut u8;
+    let address = protect_can_alter_text_segments as *const () as *mut u8;
     unsafe {
       protect(address, 1, Protection::READ_WRITE_EXECUTE).unwrap();
       *address = 0x90;
+      crate::flush_instruction_cache(address, 1).unwrap();
     }
   }
 
@@ -296,4 +304,52 @@ mod tests {
 
     Ok(())
   }
+
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn protect_rejects_exceeding_max_protection() -> Result<()> {
+    use crate::{map_file, query_detailed, Error};
+
+    let path = std::env::temp_dir().join(format!("region-max-{}", std::process::id()));
+    std::fs::write(&path, vec![0; page::size()]).map_err(Error::SystemCall)?;
+    let file = std::fs::File::open(&path).map_err(Error::SystemCall)?;
+    let memory = unsafe { map_file(&file, 0, page::size(), Protection::READ, true)? };
+
+    let max = query_detailed(memory.as_ptr::<()>())?
+      .max_protection()
+      .unwrap();
+    assert!(max.contains(Protection::READ));
+    assert!(!max.contains(Protection::WRITE));
+    assert_eq!(query(memory.as_ptr::<()>())?.max_protection(), None);
+
+    let result = unsafe { protect(memory.as_ptr::<u8>(), 1, Protection::READ_WRITE) };
+    assert!(matches!(result, Err(Error::MaxProtectionExceeded(found)) if found == max));
+
+    std::mem::drop((memory, file));
+    std::fs::remove_file(path).map_err(Error::SystemCall)
+  }
+
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn protect_accepts_linux_extensions() -> Result<()> {
+    use crate::{alloc, AllocOptions};
+
+    let memory = alloc(page::size(), Protection::READ_WRITE | Protection::SEM)?;
+    assert_eq!(
+      query(memory.as_ptr::<()>())?.protection(),
+      Protection::READ_WRITE
+    );
+
+    let memory = AllocOptions::new()
+      .grows_down(true)
+      .alloc(page::size() * 2, Protection::READ_WRITE)?;
+    let last_page = unsafe { memory.as_ptr::<u8>().add(page::size()) };
+
+    // The change extends to the start of the region
+    unsafe { protect(last_page, 1, Protection::READ | Protection::GROWSDOWN)? };
+    let region = query(memory.as_ptr::<()>())?;
+    assert_eq!(region.protection(), Protection::READ);
+    assert_eq!(region.as_range(), memory.as_range());
+    Ok(())
+  }
 }
@@ -1,4 +1,5 @@
-use crate::{os, util, Error, Region, Result};
+use crate::{os, page, util, Error, Region, Result};
+use std::ops::Range;
 
 /// An iterator over the [`Region`]s that encompass an address range.
 ///
@@ -6,6 +7,7 @@ use crate::{os, util, Error, Region, Result};
 pub struct QueryIter {
   iterator: Option<os::QueryIter>,
   origin: *const (),
+  local: bool,
 }
 
 impl QueryIter {
@@ -15,6 +17,23 @@ impl QueryIter {
     os::QueryIter::new(origin, size).map(|iterator| Self {
       iterator: Some(iterator),
       origin,
+      local: true,
+    })
+  }
+
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  pub(crate) fn with_options<T>(
+    process: Option<&crate::Process>,
+    detailed: bool,
+    origin: *const T,
+    size: usize,
+  ) -> Result<Self> {
+    let origin = origin.cast();
+
+    os::QueryIter::with_options(process, detailed, origin, size).map(|iterator| Self {
+      iterator: Some(iterator),
+      origin,
+      local: process.is_none(),
     })
   }
 }
@@ -34,7 +53,7 @@ impl Iterator for QueryIter {
 
     while let Some(result) = regions.next() {
       match result {
-        Ok(region) => {
+        Ok(mut region) => {
           let range = region.as_range();
 
           // Skip the region if it precedes the queried range
@@ -47,6 +66,17 @@ impl Iterator for QueryIter {
             break;
           }
 
+          // Uncommitted reservation pages are indistinguishable from other
+          // inaccessible pages, thus they're tracked by the crate itself. Pages
+          // that have since been made accessible (e.g. by `protect`) are in use.
+          #[cfg(unix)]
+          if self.local
+            && region.protection == crate::Protection::NONE
+            && os::is_uncommitted(&range)
+          {
+            region.reserved = true;
+          }
+
           return Some(Ok(region));
         }
         Err(error) => {
@@ -80,7 +110,12 @@ unsafe impl Sync for QueryIter {}
 /// # Errors
 ///
 /// - If an interaction with the underlying operating system fails, an error
-/// will be returned.
+///   will be returned.
+///
+/// # OS-Specific Behavior
+///
+/// On Linux 6.11+ the region is queried directly using the `PROCMAP_QUERY`
+/// ioctl, instead of parsing every entry of `/proc/self/maps`.
 ///
 /// # Examples
 ///
@@ -133,9 +168,15 @@ pub fn query<T>(address: *const T) -> Result<Region> {
 /// # Errors
 ///
 /// - If an interaction with the underlying operating system fails, an error
-/// will be returned.
+///   will be returned.
 /// - If size is zero, [`Error::InvalidParameter`] will be returned.
 ///
+/// # OS-Specific Behavior
+///
+/// On Linux 6.11+ the regions are queried one at a time using the
+/// `PROCMAP_QUERY` ioctl as the iterator advances, instead of parsing a
+/// snapshot of `/proc/self/maps` upfront.
+///
 /// # Examples
 ///
 /// ```
@@ -156,6 +197,187 @@ pub fn query_range<T>(address: *const T, size: usize) -> Result<QueryIter> {
   QueryIter::new(address, size)
 }
 
+/// Finds unmapped address space, of a given size and alignment, within a
+/// range.
+///
+/// The gaps between the mapped regions (as reported by [`query_range`]) are
+/// searched in ascending address order, and the lowest suitable address is
+/// returned. This address may then be passed to [`alloc_at`](crate::alloc_at).
+/// If no gap within the range is large enough, `None` is returned.
+///
+/// Note that the memory map may change at any time (e.g. by another thread
+/// allocating memory), thus the address space is not guaranteed to remain
+/// free once this function returns.
+///
+/// # Parameters
+///
+/// - The size may not be zero.
+/// - The size is rounded up to the closest page boundary.
+/// - The alignment must be a power of two, and is raised to the operating
+///   system's page size if smaller.
+/// - The range may not be empty. The null address is never returned.
+///
+/// # Errors
+///
+/// - If an interaction with the underlying operating system fails, an error
+///   will be returned.
+/// - If size is zero, the alignment is not a power of two, or the range is
+///   empty, [`Error::InvalidParameter`] will be returned.
+///
+/// # OS-Specific Behavior
+///
+/// Addresses that are reserved by the OS, but not reported as mapped, are not
+/// excluded (e.g. below `vm.mmap_min_addr` on Linux), and should be omitted
+/// from the range. On Windows, new allocations are aligned to the allocation
+/// granularity (most commonly 64KB), which the alignment should reflect.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> region::Result<()> {
+/// use region::Protection;
+///
+/// let (size, alignment) = (region::page::size() * 4, 0x10_0000);
+/// let range = 0x1000_0000..0x7000_0000;
+///
+/// if let Some(address) = region::find_free(size, alignment, range)? {
+///   let memory = region::alloc_at(address, size, Protection::READ_WRITE)?;
+///   assert_eq!(memory.as_ptr(), address);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[inline]
+pub fn find_free(
+  size: usize,
+  alignment: usize,
+  search_range: Range<usize>,
+) -> Result<Option<*const ()>> {
+  if size == 0 {
+    return Err(Error::InvalidParameter("size"));
+  }
+
+  if !alignment.is_power_of_two() {
+    return Err(Error::InvalidParameter("alignment"));
+  }
+
+  if search_range.start >= search_range.end {
+    return Err(Error::InvalidParameter("search_range"));
+  }
+
+  let size = page::ceil(size as *const ()) as usize;
+  let mask = alignment.max(page::size()) - 1;
+  let align_up = |address: usize| address.checked_add(mask).map(|address| address & !mask);
+  let fits =
+    |address: usize, end: usize| matches!(address.checked_add(size), Some(last) if last <= end);
+
+  let mut candidate = align_up(search_range.start.max(1));
+
+  for region in query_range(search_range.start as *const (), search_range.len())? {
+    let range = region?.as_range();
+
+    match candidate {
+      Some(address) if fits(address, range.start.min(search_range.end)) => {
+        return Ok(Some(address as *const ()));
+      }
+      Some(address) => candidate = align_up(address.max(range.end)),
+      None => break,
+    }
+  }
+
+  // The gap following the last region extends to the end of the range
+  Ok(
+    candidate
+      .filter(|&address| fits(address, search_range.end))
+      .map(|address| address as *const ()),
+  )
+}
+
+/// Queries the OS with an address, returning the region it resides within,
+/// including its memory usage.
+///
+/// This is the equivalent of [`query`], except that [`Region::usage`] is
+/// available for the returned region.
+///
+/// # Implementation
+///
+/// This function is implemented using `/proc/self/smaps`, which is
+/// significantly slower to read than `/proc/self/maps`, since the kernel must
+/// walk the page tables of each mapping.
+///
+/// # Errors
+///
+/// - If an interaction with the underlying operating system fails, an error
+///   will be returned.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> region::Result<()> {
+/// let data = vec![1u8; 100];
+/// let region = region::query_detailed(data.as_ptr())?;
+/// let usage = region.usage().unwrap();
+///
+/// assert!(usage.rss() > 0);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[inline]
+pub fn query_detailed<T>(address: *const T) -> Result<Region> {
+  let (address, size) = util::round_to_page_boundaries(address, 1)?;
+
+  QueryIter::with_options(None, true, address, size)?
+    .next()
+    .ok_or(Error::UnmappedRegion)?
+}
+
+/// Queries the OS for mapped regions that overlap with the specified range,
+/// including their memory usage.
+///
+/// This is the equivalent of [`query_range`], except that [`Region::usage`] is
+/// available for each returned region. See [`query_detailed`] for the
+/// performance implications.
+///
+/// # Errors
+///
+/// - If an interaction with the underlying operating system fails, an error
+///   will be returned.
+/// - If size is zero, [`Error::InvalidParameter`] will be returned.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[inline]
+pub fn query_range_detailed<T>(address: *const T, size: usize) -> Result<QueryIter> {
+  let (address, size) = util::round_to_page_boundaries(address, size)?;
+  QueryIter::with_options(None, true, address, size)
+}
+
+/// Queries the OS for the memory usage totals of the process.
+///
+/// # Implementation
+///
+/// This function is implemented using `/proc/self/smaps_rollup`, falling back
+/// to summing up the entries of `/proc/self/smaps` on kernels without it.
+///
+/// # Errors
+///
+/// - If an interaction with the underlying operating system fails, an error
+///   will be returned.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> region::Result<()> {
+/// let usage = region::query_usage()?;
+/// assert!(usage.rss() >= usage.private_dirty());
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[inline]
+pub fn query_usage() -> Result<crate::MemoryUsage> {
+  os::query_usage(None)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -168,9 +390,16 @@ mod tests {
     Protection::READ_EXECUTE
   };
 
+  // Above the address space reserved by executables (e.g. `__PAGEZERO`)
+  const FREE_SEARCH_BASE: usize = if cfg!(target_pointer_width = "64") {
+    0x10_0000_0000_u64 as usize
+  } else {
+    0x1000_0000
+  };
+
   #[test]
   fn query_returns_unmapped_for_oob_address() {
-    let (min, max) = (std::ptr::null::<()>(), usize::max_value() as *const ());
+    let (min, max) = (std::ptr::null::<()>(), usize::MAX as *const ());
     assert!(matches!(query(min), Err(Error::UnmappedRegion)));
     assert!(matches!(query(max), Err(Error::UnmappedRegion)));
   }
@@ -185,6 +414,21 @@ mod tests {
     Ok(())
   }
 
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn query_returns_backing_file_for_text_segment() -> Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let region = query(query_returns_backing_file_for_text_segment as *const ())?;
+    let file = region.file().expect("text segment is file-backed");
+    let metadata = std::fs::metadata(file.path()).map_err(Error::SystemCall)?;
+
+    assert_eq!(file.inode(), metadata.ino());
+    assert_eq!(file.device(), metadata.dev());
+    assert_eq!(region.name(), None);
+    Ok(())
+  }
+
   #[test]
   fn query_returns_one_region_for_multiple_page_allocation() -> Result<()> {
     let alloc = crate::alloc(page::size() + 1, Protection::READ_EXECUTE)?;
@@ -216,6 +460,35 @@ mod tests {
     Ok(())
   }
 
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn query_detailed_returns_memory_usage() -> Result<()> {
+    let mut memory = crate::alloc(page::size() * 4, Protection::READ_WRITE)?;
+    unsafe { *memory.as_mut_ptr::<u8>() = 1 };
+
+    let region = query_detailed(memory.as_ptr::<()>())?;
+    assert_eq!(region.as_range(), query(memory.as_ptr::<()>())?.as_range());
+
+    let usage = region.usage().expect("detailed region has usage");
+    assert!(usage.rss() >= page::size());
+    assert!(usage.private_dirty() >= page::size());
+    assert!(query(memory.as_ptr::<()>())?.usage().is_none());
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn query_usage_returns_process_totals() -> Result<()> {
+    let usage = query_usage()?;
+    let regions =
+      query_range_detailed(std::ptr::null::<()>(), usize::MAX)?.collect::<Result<Vec<_>>>()?;
+
+    assert!(usage.rss() > 0);
+    assert!(usage.rss() >= usage.anonymous());
+    assert!(regions.iter().all(|region| region.usage().is_some()));
+    Ok(())
+  }
+
   #[test]
   fn query_range_does_not_return_unmapped_regions() -> Result<()> {
     let regions = query_range(std::ptr::null::<()>(), 1)?.collect::<Result<Vec<_>>>()?;
@@ -257,8 +530,7 @@ mod tests {
 
   #[test]
   fn query_range_can_iterate_over_entire_process() -> Result<()> {
-    let regions =
-      query_range(std::ptr::null::<()>(), usize::max_value())?.collect::<Result<Vec<_>>>()?;
+    let regions = query_range(std::ptr::null::<()>(), usize::MAX)?.collect::<Result<Vec<_>>>()?;
 
     // This test is a bit rough around the edges
     assert!(regions
@@ -292,4 +564,69 @@ mod tests {
     assert_eq!(iter.next().transpose()?, None);
     Ok(())
   }
+
+  #[test]
+  fn find_free_rejects_invalid_parameters() {
+    let range = 0..usize::MAX;
+    assert!(matches!(
+      find_free(0, 1, range.clone()),
+      Err(Error::InvalidParameter("size"))
+    ));
+    assert!(matches!(
+      find_free(1, 3, range),
+      Err(Error::InvalidParameter("alignment"))
+    ));
+    assert!(matches!(
+      find_free(1, 1, 0x1000..0x1000),
+      Err(Error::InvalidParameter("search_range"))
+    ));
+  }
+
+  #[test]
+  fn find_free_returns_aligned_unmapped_address() -> Result<()> {
+    // Each test searches a distinct range, since `alloc_at` replaces mappings
+    let alignment = 0x10_0000;
+    let address = find_free(
+      page::size() * 2,
+      alignment,
+      FREE_SEARCH_BASE..FREE_SEARCH_BASE + 0x1000_0000,
+    )?
+    .unwrap();
+    assert_eq!(address as usize & (alignment - 1), 0);
+    assert!(matches!(query(address), Err(Error::UnmappedRegion)));
+
+    let memory = crate::alloc_at(address, page::size() * 2, Protection::READ_WRITE)?;
+    assert_eq!(memory.as_ptr(), address);
+
+    let range = memory.as_range();
+    assert_eq!(find_free(1, 1, range.clone())?, None);
+    assert_eq!(
+      find_free(1, 1, range.start..range.end + page::size())?,
+      Some(range.end as *const ())
+    );
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn find_free_searches_gaps_between_regions() -> Result<()> {
+    let address = find_free(
+      page::size() * 3,
+      1,
+      FREE_SEARCH_BASE + 0x1000_0000..FREE_SEARCH_BASE + 0x2000_0000,
+    )?
+    .unwrap();
+    let memory = crate::alloc_at(address, page::size() * 3, Protection::READ_WRITE)?;
+    let range = memory.as_range();
+
+    let (_head, rest) = memory.split_at(page::size()).ok().unwrap();
+    let (middle, _tail) = rest.split_at(page::size()).ok().unwrap();
+    let gap = middle.as_ptr::<()>();
+    std::mem::drop(middle);
+
+    assert_eq!(find_free(page::size(), 1, range.clone())?, Some(gap));
+    assert_eq!(find_free(page::size() * 2, 1, range.clone())?, None);
+    assert_eq!(find_free(1, page::size() * 2, range)?, None);
+    Ok(())
+  }
 }
@@ -0,0 +1,287 @@
+use crate::{os, page, util, Error, Protection, Result};
+
+/// A handle to an owned range of reserved address space.
+///
+/// Reserved address space is not backed by memory, nor accounted towards the
+/// process' commit charge, until it's committed. This allows claiming a large
+/// contiguous range upfront, and committing parts of it on demand.
+///
+/// The range is released once the handle is dropped.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> region::Result<()> {
+/// use region::Protection;
+///
+/// let mut reservation = region::reserve(region::page::size() * 1024)?;
+/// reservation.commit(0, region::page::size(), Protection::READ_WRITE)?;
+///
+/// let data = reservation.as_ptr::<u8>() as *mut u8;
+/// unsafe { *data = 0xFF };
+///
+/// reservation.decommit(0, region::page::size())?;
+/// # Ok(())
+/// # }
+/// ```
+#[allow(clippy::len_without_is_empty)]
+pub struct Reservation {
+  base: *const (),
+  size: usize,
+}
+
+impl Reservation {
+  /// Returns a pointer to the reservation's base address.
+  ///
+  /// The address is always aligned to the operating system's page size.
+  #[inline(always)]
+  pub fn as_ptr<T>(&self) -> *const T {
+    self.base.cast()
+  }
+
+  /// Returns two raw pointers spanning the reservation's address space.
+  #[inline(always)]
+  pub fn as_ptr_range<T>(&self) -> std::ops::Range<*const T> {
+    let range = self.as_range();
+    (range.start as *const T)..(range.end as *const T)
+  }
+
+  /// Returns a range spanning the reservation's address space.
+  #[inline(always)]
+  pub fn as_range(&self) -> std::ops::Range<usize> {
+    (self.base as usize)..(self.base as usize).saturating_add(self.size)
+  }
+
+  /// Returns the size of the reservation in bytes.
+  ///
+  /// The size is always aligned to a multiple of the operating system's page
+  /// size.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.size
+  }
+
+  /// Commits a subrange of the reservation, with a defined protection.
+  ///
+  /// Newly committed pages are zero-initialized, whilst pages that are already
+  /// committed retain their contents, but have their protection changed.
+  ///
+  /// # Parameters
+  ///
+  /// - The offset is rounded down to the closest page boundary.
+  /// - The length may not be zero.
+  /// - The length is rounded up to the closest page boundary, relative to the
+  ///   offset.
+  ///
+  /// # Errors
+  ///
+  /// - If an interaction with the underlying operating system fails, an error
+  ///   will be returned.
+  /// - If the length is zero, or the subrange exceeds the reservation,
+  ///   [`Error::InvalidParameter`] will be returned.
+  ///
+  /// # Implementation
+  ///
+  /// This function is implemented using `VirtualAlloc` with `MEM_COMMIT` on
+  /// Windows, and `mmap` with `MAP_FIXED` on POSIX.
+  #[inline]
+  pub fn commit(&mut self, offset: usize, len: usize, protection: Protection) -> Result<()> {
+    let (address, size) = util::round_subrange_to_page_boundaries(self.as_range(), offset, len)?;
+    unsafe { os::commit(address, size, protection) }
+  }
+
+  /// Decommits a subrange of the reservation.
+  ///
+  /// The contents of the pages are discarded, and the pages are no longer
+  /// accessible, although the address space remains reserved.
+  ///
+  /// # Parameters
+  ///
+  /// See [`Reservation::commit`].
+  ///
+  /// # Errors
+  ///
+  /// See [`Reservation::commit`].
+  ///
+  /// # Implementation
+  ///
+  /// This function is implemented using `VirtualFree` with `MEM_DECOMMIT` on
+  /// Windows, and `mmap` with `MAP_FIXED` and `PROT_NONE` on POSIX.
+  #[inline]
+  pub fn decommit(&mut self, offset: usize, len: usize) -> Result<()> {
+    let (address, size) = util::round_subrange_to_page_boundaries(self.as_range(), offset, len)?;
+    unsafe { os::decommit(address, size) }
+  }
+}
+
+impl Drop for Reservation {
+  #[inline]
+  fn drop(&mut self) {
+    let result = unsafe { os::release(self.base, self.size) };
+    debug_assert!(result.is_ok(), "releasing reservation: {:?}", result);
+  }
+}
+
+unsafe impl Send for Reservation {}
+unsafe impl Sync for Reservation {}
+
+/// Reserves a range of address space, without committing any memory.
+///
+/// The reserved pages are inaccessible, and are not accounted towards the
+/// process' commit charge, until committed using [`Reservation::commit`]. The
+/// reservation address will be decided by the operating system.
+///
+/// # Parameters
+///
+/// - The size may not be zero.
+/// - The size is rounded up to the closest page boundary.
+///
+/// # Errors
+///
+/// - If an interaction with the underlying operating system fails, an error
+///   will be returned.
+/// - If size is zero, [`Error::InvalidParameter`] will be returned.
+///
+/// # OS-Specific Behavior
+///
+/// On Unix systems, [`query`](crate::query) reports uncommitted parts of
+/// reservations (created by this crate) as not committed, similar to
+/// `MEM_RESERVE` pages on Windows.
+///
+/// # Implementation
+///
+/// This function is implemented using `VirtualAlloc` with `MEM_RESERVE` on
+/// Windows, and `mmap` with `PROT_NONE` and `MAP_NORESERVE` (where available)
+/// on POSIX.
+#[inline]
+pub fn reserve(size: usize) -> Result<Reservation> {
+  if size == 0 {
+    return Err(Error::InvalidParameter("size"));
+  }
+
+  let size = page::ceil(size as *const ()) as usize;
+
+  unsafe {
+    let base = os::reserve(size)?;
+    Ok(Reservation { base, size })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reserve_is_not_committed() -> Result<()> {
+    let reservation = reserve(page::size() * 4)?;
+    let region = crate::query(reservation.as_ptr::<()>())?;
+
+    assert!(!region.is_committed());
+    assert_eq!(region.protection(), Protection::NONE);
+    Ok(())
+  }
+
+  #[test]
+  fn reserve_commit_provides_accessible_memory() -> Result<()> {
+    let mut reservation = reserve(page::size() * 4)?;
+    reservation.commit(page::size(), page::size(), Protection::READ_WRITE)?;
+
+    let data = unsafe { reservation.as_ptr::<u8>().add(page::size()) as *mut u8 };
+    unsafe { *data = 0xFF };
+
+    let region = crate::query(data)?;
+    assert!(region.is_committed());
+    assert_eq!(region.protection(), Protection::READ_WRITE);
+    assert_eq!(region.len(), page::size());
+
+    assert!(!crate::query(reservation.as_ptr::<()>())?.is_committed());
+    Ok(())
+  }
+
+  #[test]
+  fn reserve_decommit_discards_contents() -> Result<()> {
+    let mut reservation = reserve(page::size() * 2)?;
+    reservation.commit(0, page::size() * 2, Protection::READ_WRITE)?;
+
+    let data = reservation.as_ptr::<u8>() as *mut u8;
+    unsafe { *data = 0xFF };
+
+    reservation.decommit(0, 1)?;
+    let region = crate::query(data)?;
+    assert!(!region.is_committed());
+    assert_eq!(region.protection(), Protection::NONE);
+
+    reservation.commit(0, 1, Protection::READ_WRITE)?;
+    assert_eq!(unsafe { *data }, 0);
+    Ok(())
+  }
+
+  #[test]
+  fn reserve_commit_retains_committed_contents() -> Result<()> {
+    let mut reservation = reserve(page::size() * 2)?;
+    reservation.commit(0, page::size(), Protection::READ_WRITE)?;
+
+    let data = reservation.as_ptr::<u8>() as *mut u8;
+    unsafe { *data = 0xFF };
+
+    reservation.commit(0, page::size() * 2, Protection::READ)?;
+    assert_eq!(unsafe { *data }, 0xFF);
+    assert_eq!(crate::query(data)?.protection(), Protection::READ);
+    Ok(())
+  }
+
+  #[test]
+  fn reserve_commit_state_survives_merged_and_split_regions() -> Result<()> {
+    let mut reservation = reserve(page::size() * 3)?;
+    let middle = unsafe { reservation.as_ptr::<u8>().add(page::size()) };
+
+    // The decommitted page may be merged with its uncommitted neighbors
+    reservation.commit(page::size(), 1, Protection::READ_WRITE)?;
+    reservation.decommit(page::size(), 1)?;
+    assert!(!crate::query(middle)?.is_committed());
+    assert!(!crate::query(reservation.as_ptr::<()>())?.is_committed());
+
+    // Accessible pages are in use, even if they're never committed explicitly
+    unsafe { crate::protect(middle, 1, Protection::READ)? };
+    assert!(crate::query(middle)?.is_committed());
+    assert!(!crate::query(reservation.as_ptr::<()>())?.is_committed());
+    Ok(())
+  }
+
+  #[test]
+  fn reserve_rejects_invalid_subranges() -> Result<()> {
+    let mut reservation = reserve(page::size() * 2)?;
+
+    assert!(matches!(reserve(0), Err(Error::InvalidParameter(_))));
+    assert!(matches!(
+      reservation.commit(0, 0, Protection::READ),
+      Err(Error::InvalidParameter(_))
+    ));
+    assert!(matches!(
+      reservation.commit(page::size() * 2, 1, Protection::READ),
+      Err(Error::InvalidParameter(_))
+    ));
+    assert!(matches!(
+      reservation.decommit(page::size(), page::size() + 1),
+      Err(Error::InvalidParameter(_))
+    ));
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  fn reserve_only_accounts_committed_memory() -> Result<()> {
+    use crate::VmFlags;
+
+    let mut reservation = reserve(page::size() * 2)?;
+    reservation.commit(0, page::size(), Protection::READ_WRITE)?;
+
+    let committed = crate::query_detailed(reservation.as_ptr::<()>())?;
+    let uncommitted = crate::query_detailed(reservation.as_ptr::<u8>().wrapping_add(page::size()))?;
+
+    assert!(committed.vm_flags().unwrap().contains(VmFlags::ACCOUNT));
+    assert!(uncommitted.vm_flags().unwrap().contains(VmFlags::NORESERVE));
+    assert!(!uncommitted.is_committed());
+    Ok(())
+  }
+}
@@ -0,0 +1,323 @@
+use crate::{query_range, Region, Result};
+use std::ops::Range;
+
+/// An immutable snapshot of a process' memory map.
+///
+/// The snapshot contains all mapped [`Region`]s, sorted by their address. Once
+/// created, it's never updated, and thus lookups do not interact with the OS.
+///
+/// This `struct` is created by [`snapshot`]. See its documentation for more.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryMap {
+  regions: Vec<Region>,
+}
+
+impl MemoryMap {
+  /// Creates a snapshot from regions, sorting them by their address.
+  pub(crate) fn from_regions(mut regions: Vec<Region>) -> Self {
+    regions.sort_by_key(|region| region.as_range().start);
+    Self { regions }
+  }
+
+  /// Returns the region that an address resides within, if any.
+  ///
+  /// This lookup is performed in `O(log n)` time.
+  #[inline]
+  pub fn find<T>(&self, address: *const T) -> Option<&Region> {
+    let address = address as usize;
+    let index = self
+      .regions
+      .partition_point(|region| region.as_range().start <= address);
+
+    self.regions[..index]
+      .last()
+      .filter(|region| region.as_range().contains(&address))
+  }
+
+  /// Returns the regions that overlap with an address range.
+  ///
+  /// The first and last regions may extend beyond the range. This lookup is
+  /// performed in `O(log n)` time.
+  #[inline]
+  pub fn range(&self, range: Range<usize>) -> &[Region] {
+    let start = self
+      .regions
+      .partition_point(|region| region.as_range().end <= range.start);
+    let end = self
+      .regions
+      .partition_point(|region| region.as_range().start < range.end);
+
+    &self.regions[start..end.max(start)]
+  }
+
+  /// Returns an iterator over the regions, in ascending address order.
+  #[inline]
+  pub fn iter(&self) -> std::slice::Iter<'_, Region> {
+    self.regions.iter()
+  }
+
+  /// Returns the regions, in ascending address order.
+  #[inline(always)]
+  pub fn as_slice(&self) -> &[Region] {
+    &self.regions
+  }
+
+  /// Returns the number of regions.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.regions.len()
+  }
+
+  /// Returns whether the snapshot is empty or not.
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.regions.is_empty()
+  }
+
+  /// Compares the snapshot with a later snapshot, returning the changes of
+  /// its regions in ascending address order.
+  ///
+  /// Regions are identified by their base address, and compared using their
+  /// equality. Changing the properties of a part of a region splits it (e.g.
+  /// using [`protect`](crate::protect)), which is reported as the region being
+  /// resized, and the remaining parts being added.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// # fn main() -> region::Result<()> {
+  /// use region::{Protection, RegionChange};
+  ///
+  /// let before = region::snapshot()?;
+  /// let memory = region::alloc(1, Protection::READ_WRITE)?;
+  /// let after = region::snapshot()?;
+  ///
+  /// assert!(before.diff(&after).iter().any(|change| match change {
+  ///   RegionChange::Added(region) => region.as_range().contains(&memory.as_range().start),
+  ///   _ => false,
+  /// }));
+  /// # Ok(())
+  /// # }
+  /// ```
+  #[inline]
+  pub fn diff(&self, later: &MemoryMap) -> Vec<RegionChange> {
+    let mut changes = Vec::new();
+    let mut before = self.regions.iter().peekable();
+    let mut after = later.regions.iter().peekable();
+
+    loop {
+      let change = match (before.peek(), after.peek()) {
+        (Some(old), Some(new)) if old.base == new.base => {
+          let (old, new) = (before.next().unwrap(), after.next().unwrap());
+
+          if old == new {
+            continue;
+          }
+
+          RegionChange::Changed {
+            before: old.clone(),
+            after: new.clone(),
+          }
+        }
+        (Some(old), Some(new)) if (old.base as usize) < (new.base as usize) => {
+          RegionChange::Removed(before.next().unwrap().clone())
+        }
+        (Some(_), Some(_)) | (None, Some(_)) => RegionChange::Added(after.next().unwrap().clone()),
+        (Some(_), None) => RegionChange::Removed(before.next().unwrap().clone()),
+        (None, None) => break,
+      };
+
+      changes.push(change);
+    }
+
+    changes
+  }
+}
+
+/// A change of a region, between two snapshots of a memory map.
+///
+/// This is obtained using [`MemoryMap::diff`].
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegionChange {
+  /// The region has been mapped.
+  Added(Region),
+  /// The region has been unmapped.
+  Removed(Region),
+  /// The region's base address is retained, but its properties have changed.
+  Changed {
+    /// The region, prior to the change.
+    before: Region,
+    /// The region, after the change.
+    after: Region,
+  },
+}
+
+impl RegionChange {
+  /// Returns whether the region has changed size.
+  #[inline]
+  pub fn is_resized(&self) -> bool {
+    matches!(self, RegionChange::Changed { before, after } if before.len() != after.len())
+  }
+
+  /// Returns whether the region has changed protection.
+  #[inline]
+  pub fn is_protection_changed(&self) -> bool {
+    matches!(
+      self,
+      RegionChange::Changed { before, after } if before.protection() != after.protection()
+    )
+  }
+
+  /// Returns whether the region has changed between private and shared.
+  #[inline]
+  pub fn is_sharing_changed(&self) -> bool {
+    matches!(
+      self,
+      RegionChange::Changed { before, after } if before.is_shared() != after.is_shared()
+    )
+  }
+}
+
+impl<'a> IntoIterator for &'a MemoryMap {
+  type Item = &'a Region;
+  type IntoIter = std::slice::Iter<'a, Region>;
+
+  #[inline]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl IntoIterator for MemoryMap {
+  type Item = Region;
+  type IntoIter = std::vec::IntoIter<Region>;
+
+  #[inline]
+  fn into_iter(self) -> Self::IntoIter {
+    self.regions.into_iter()
+  }
+}
+
+/// Queries the OS for a snapshot of the process' memory map.
+///
+/// Every [`query`](crate::query) reads the memory map anew, whereas the
+/// returned [`MemoryMap`] is read once, and supports efficient lookups of
+/// addresses thereafter. The snapshot does not reflect any subsequent changes
+/// to the memory map.
+///
+/// # Errors
+///
+/// - If an interaction with the underlying operating system fails, an error
+///   will be returned.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> region::Result<()> {
+/// let data = [0; 100];
+/// let map = region::snapshot()?;
+///
+/// let region = map.find(data.as_ptr()).unwrap();
+/// assert_eq!(region.protection(), region::Protection::READ_WRITE);
+/// # Ok(())
+/// # }
+/// ```
+#[inline]
+pub fn snapshot() -> Result<MemoryMap> {
+  query_range(std::ptr::null::<()>(), usize::MAX)?
+    .collect::<Result<Vec<_>>>()
+    .map(MemoryMap::from_regions)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{alloc, page, Protection};
+
+  #[test]
+  fn snapshot_is_sorted_by_address() -> Result<()> {
+    let map = snapshot()?;
+    assert!(!map.is_empty());
+    assert!(map
+      .as_slice()
+      .windows(2)
+      .all(|pair| pair[0].as_range().end <= pair[1].as_range().start));
+    assert_eq!(map.iter().count(), map.len());
+    Ok(())
+  }
+
+  #[test]
+  fn snapshot_can_find_address() -> Result<()> {
+    let memory = alloc(page::size() * 2, Protection::READ_EXECUTE)?;
+    let map = snapshot()?;
+
+    let region = map.find(memory.as_ptr::<u8>()).unwrap();
+    assert_eq!(region, &crate::query(memory.as_ptr::<u8>())?);
+
+    let last_byte = (memory.as_range().end - 1) as *const u8;
+    assert_eq!(map.find(last_byte), Some(region));
+    assert_eq!(map.find(std::ptr::null::<()>()), None);
+    Ok(())
+  }
+
+  #[test]
+  fn snapshot_can_find_range() -> Result<()> {
+    let mut memory = alloc(page::size() * 3, Protection::READ_WRITE)?;
+    memory.protect_range(page::size(), 1, Protection::READ)?;
+    let map = snapshot()?;
+
+    let range = memory.as_range();
+    let regions = map.range(range.start + 1..range.end - 1);
+    assert_eq!(regions.len(), 3);
+    assert_eq!(regions[1].protection(), Protection::READ);
+
+    let regions = map.range(range.start + page::size()..range.start + page::size() + 1);
+    assert_eq!(regions.len(), 1);
+    assert_eq!(regions[0].protection(), Protection::READ);
+
+    assert!(map.range(range.start..range.start).is_empty());
+    assert!(map.range(0..page::size()).is_empty());
+    Ok(())
+  }
+
+  #[test]
+  fn snapshot_is_send_and_sync() {
+    fn assert_send_sync<T: Clone + Send + Sync>() {}
+    assert_send_sync::<MemoryMap>();
+  }
+
+  #[test]
+  fn snapshot_diff_reports_changes() -> Result<()> {
+    let mut memory = alloc(page::size() * 3, Protection::READ_WRITE)?;
+    let range = memory.as_range();
+
+    // Only the allocation's regions are compared, excluding unrelated changes
+    let snapshot_allocation = || {
+      Ok(MemoryMap::from_regions(
+        snapshot()?.range(range.clone()).to_vec(),
+      ))
+    };
+
+    let before: MemoryMap = snapshot_allocation()?;
+    memory.protect_range(page::size(), 1, Protection::READ)?;
+    let after = snapshot_allocation()?;
+    assert!(before.diff(&before).is_empty());
+
+    let changes = before.diff(&after);
+    assert_eq!(changes.len(), 3);
+    assert!(changes[0].is_resized());
+    assert!(!changes[0].is_protection_changed());
+    assert!(
+      matches!(&changes[1], RegionChange::Added(region) if region.protection() == Protection::READ)
+    );
+    assert!(matches!(changes[2], RegionChange::Added(_)));
+
+    let changes = after.diff(&before);
+    assert_eq!(changes.len(), 3);
+    assert!(changes[0].is_resized());
+    assert!(matches!(changes[1], RegionChange::Removed(_)));
+    assert!(matches!(changes[2], RegionChange::Removed(_)));
+    Ok(())
+  }
+}
@@ -1,4 +1,8 @@
 use crate::{page, Error, Result};
+#[cfg(unix)]
+use std::collections::BTreeMap;
+#[cfg(unix)]
+use std::ops::Range;
 
 /// Validates & rounds an address-size pair to their respective page boundary.
 pub fn round_to_page_boundaries<T>(address: *const T, size: usize) -> Result<(*const T, usize)> {
@@ -11,6 +15,93 @@ pub fn round_to_page_boundaries<T>(address: *const T, size: usize) -> Result<(*c
   Ok((page::floor(address), size))
 }
 
+/// Validates & rounds an offset-length pair, within a page aligned range, to
+/// their respective page boundary.
+pub fn round_subrange_to_page_boundaries(
+  range: std::ops::Range<usize>,
+  offset: usize,
+  len: usize,
+) -> Result<(*const (), usize)> {
+  if offset >= range.len() {
+    return Err(Error::InvalidParameter("offset"));
+  }
+
+  let address = (range.start + offset) as *const ();
+  let (address, size) = round_to_page_boundaries(address, len)?;
+
+  if (address as usize).saturating_add(size) > range.end {
+    return Err(Error::InvalidParameter("len"));
+  }
+
+  Ok((address, size))
+}
+
+/// A set of disjoint address ranges.
+///
+/// Each range is kept exactly as inserted (i.e. adjacent ranges are not
+/// coalesced), unless it's partially removed.
+#[cfg(unix)]
+#[derive(Debug, Default)]
+pub struct RangeSet {
+  /// The ranges, mapping each start address to its end address
+  ranges: BTreeMap<usize, usize>,
+}
+
+#[cfg(unix)]
+impl RangeSet {
+  /// Adds a range to the set, replacing any overlapping parts of other ranges.
+  pub fn insert(&mut self, range: Range<usize>) {
+    self.remove(range.clone());
+    self.ranges.insert(range.start, range.end);
+  }
+
+  /// Removes a range from the set, splitting any partially overlapping ranges.
+  pub fn remove(&mut self, range: Range<usize>) {
+    for other in self.overlapping(range.clone()) {
+      let other_end = self.ranges.remove(&other.start).unwrap_or(other.end);
+
+      if other.start < range.start {
+        self.ranges.insert(other.start, range.start);
+      }
+
+      if other_end > range.end {
+        self.ranges.insert(range.end, other_end);
+      }
+    }
+  }
+
+  /// Returns whether a range is entirely contained within the set, possibly
+  /// spanning multiple adjacent ranges.
+  pub fn contains(&self, range: &Range<usize>) -> bool {
+    let mut cursor = match self.ranges.range(..=range.start).next_back() {
+      Some((_, &end)) if end > range.start => end,
+      _ => return range.is_empty(),
+    };
+
+    while cursor < range.end {
+      match self.ranges.get(&cursor) {
+        Some(&end) => cursor = end,
+        None => return false,
+      }
+    }
+
+    true
+  }
+
+  /// Returns the ranges of the set that overlap a range (unclipped).
+  pub fn overlapping(&self, range: Range<usize>) -> Vec<Range<usize>> {
+    let mut result = self
+      .ranges
+      .range(..range.end)
+      .rev()
+      .take_while(|(_, &end)| end > range.start)
+      .map(|(&start, &end)| start..end)
+      .collect::<Vec<_>>();
+    result.reverse();
+    result
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -33,4 +124,29 @@ mod tests {
     }
     Ok(())
   }
+
+  #[test]
+  #[cfg(unix)]
+  fn range_set_keeps_exact_and_splits_ranges() {
+    let mut set = RangeSet::default();
+    set.insert(10..20);
+    set.insert(30..40);
+    set.insert(20..25);
+    assert_eq!(set.overlapping(0..100), vec![10..20, 20..25, 30..40]);
+    assert!(set.contains(&(10..25)));
+    assert!(!set.contains(&(10..30)));
+
+    set.insert(5..35);
+    assert_eq!(set.overlapping(0..100), vec![5..35, 35..40]);
+    assert!(set.contains(&(5..40)));
+    assert!(!set.contains(&(4..6)));
+
+    set.remove(10..20);
+    assert_eq!(set.overlapping(0..100), vec![5..10, 20..35, 35..40]);
+    assert_eq!(set.overlapping(10..20), vec![]);
+    assert!(!set.contains(&(5..20)));
+
+    set.remove(0..100);
+    assert_eq!(set.overlapping(0..100), vec![]);
+  }
 }